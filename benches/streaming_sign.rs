@@ -0,0 +1,180 @@
+//! 流式签名与分段加密的内存基准：对一个 1 GiB 的稀疏文件签名、验证、加密并解密，
+//! 通过统计分配器记录堆内存峰值，证明内存占用与输入大小无关。
+//!
+//! 运行：`cargo bench --bench streaming_sign`
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fs::{self, File},
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use rcli::{
+    create_key, process_decrypt, process_encrypt, process_sign, process_verify, EcdsaEncoding,
+    EncryptionAlgorithm, EncryptionKey, KeyEncoding, KeyFile, KeyKind, SignatureEncoding,
+    TextSignFormat,
+};
+
+const INPUT_SIZE: u64 = 1 << 30;
+const MAX_PEAK: usize = 1 << 20;
+
+struct TrackingAllocator;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = CURRENT.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(current, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+fn reset_peak() -> usize {
+    let current = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(current, Ordering::SeqCst);
+    current
+}
+
+/// 按 `text generate` 的方式保存带类型的密钥文件。
+fn typed(kind: KeyKind, key: &[u8]) -> Vec<u8> {
+    KeyFile::new(kind, key)
+        .and_then(|key| key.encode(KeyEncoding::Raw))
+        .unwrap()
+}
+
+fn bench(format: TextSignFormat, dir: &Path, input: &str) {
+    let keys = create_key(format).expect("generate key");
+    let (sign_key, verify_key) = match (format, keys.as_slice()) {
+        (TextSignFormat::Blake3, [key]) => {
+            (typed(KeyKind::Blake3, key), typed(KeyKind::Blake3, key))
+        }
+        (TextSignFormat::Ed25519, [private, public]) => (
+            typed(KeyKind::Ed25519Private, private),
+            typed(KeyKind::Ed25519Public, public),
+        ),
+        (_, [key]) => (key.clone(), key.clone()),
+        _ => unreachable!("unexpected key material"),
+    };
+    let sign_path = dir.join(format!("{}.sign.key", format));
+    let verify_path = dir.join(format!("{}.verify.key", format));
+    fs::write(&sign_path, sign_key).unwrap();
+    fs::write(&verify_path, verify_key).unwrap();
+
+    let baseline = reset_peak();
+    let start = Instant::now();
+    let signature = process_sign(
+        input,
+        sign_path.to_str().unwrap(),
+        format,
+        SignatureEncoding::Base64,
+        EcdsaEncoding::Raw,
+    )
+    .unwrap();
+    let elapsed = start.elapsed();
+    let sign_peak = PEAK.load(Ordering::SeqCst) - baseline;
+
+    let baseline = reset_peak();
+    let valid = process_verify(
+        input,
+        verify_path.to_str().unwrap(),
+        &signature,
+        format,
+        SignatureEncoding::Base64,
+    )
+    .unwrap();
+    let verify_peak = PEAK.load(Ordering::SeqCst) - baseline;
+    assert!(valid, "{} signature must verify", format);
+
+    let throughput = INPUT_SIZE as f64 / (1 << 20) as f64 / elapsed.as_secs_f64();
+    println!(
+        "{:>8}: sign {:>8.2?} ({:.0} MiB/s), peak heap sign {} B / verify {} B",
+        format, elapsed, throughput, sign_peak, verify_peak
+    );
+    assert!(
+        sign_peak < MAX_PEAK && verify_peak < MAX_PEAK,
+        "{} used more than {} bytes of heap for a {} byte input",
+        format,
+        MAX_PEAK,
+        INPUT_SIZE
+    );
+}
+
+fn bench_encrypt(dir: &Path, input: &str) {
+    let key = create_key(TextSignFormat::ChaCha20).expect("generate key");
+    let key_path = dir.join("base64.key");
+    fs::write(&key_path, typed(KeyKind::ChaCha20, &key[0])).unwrap();
+    let key_path = key_path.to_str().unwrap().to_string();
+    let encrypted = dir.join("input.enc");
+    let decrypted = dir.join("input.dec");
+
+    let baseline = reset_peak();
+    let start = Instant::now();
+    process_encrypt(
+        input,
+        encrypted.to_str(),
+        EncryptionKey::File(key_path.clone()),
+        EncryptionAlgorithm::XChaCha20Poly1305,
+        b"",
+    )
+    .unwrap();
+    let elapsed = start.elapsed();
+    let encrypt_peak = PEAK.load(Ordering::SeqCst) - baseline;
+
+    let baseline = reset_peak();
+    process_decrypt(
+        encrypted.to_str().unwrap(),
+        decrypted.to_str(),
+        EncryptionKey::File(key_path),
+        b"",
+    )
+    .unwrap();
+    let decrypt_peak = PEAK.load(Ordering::SeqCst) - baseline;
+    assert_eq!(fs::metadata(&decrypted).unwrap().len(), INPUT_SIZE);
+
+    let throughput = INPUT_SIZE as f64 / (1 << 20) as f64 / elapsed.as_secs_f64();
+    println!(
+        "{:>8}: encrypt {:>8.2?} ({:.0} MiB/s), peak heap encrypt {} B / decrypt {} B",
+        "xchacha", elapsed, throughput, encrypt_peak, decrypt_peak
+    );
+    assert!(
+        encrypt_peak < MAX_PEAK && decrypt_peak < MAX_PEAK,
+        "encryption used more than {} bytes of heap for a {} byte input",
+        MAX_PEAK,
+        INPUT_SIZE
+    );
+}
+
+fn main() {
+    let dir = std::env::temp_dir().join(format!("rcli-bench-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("input.bin");
+    // 稀疏文件：不占用磁盘空间，读取时全部为 0
+    File::create(&input).unwrap().set_len(INPUT_SIZE).unwrap();
+    let input = input.to_str().unwrap().to_string();
+
+    for format in [
+        TextSignFormat::Blake3,
+        TextSignFormat::Ed25519,
+        TextSignFormat::HmacSha256,
+    ] {
+        bench(format, &dir, &input);
+    }
+    bench_encrypt(&dir, &input);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
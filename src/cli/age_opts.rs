@@ -0,0 +1,129 @@
+use clap::{ArgGroup, Parser};
+
+use super::{file_check, write_private, PassphraseOpts};
+
+/// `AgeCmd` 是 age v1 文件格式（age-encryption.org/v1）相关的子命令。
+/// * `Encrypt` - 为 X25519 接收方或口令加密。
+/// * `Decrypt` - 使用身份文件或口令解密。
+/// * `Keygen` - 生成新的 X25519 身份。
+#[derive(Debug, Parser)]
+#[enum_dispatch::enum_dispatch(CmdEexector)]
+pub enum AgeCmd {
+    #[command(about = "Encrypt to age recipients or a passphrase")]
+    Encrypt(AgeEncryptOpts),
+    #[command(about = "Decrypt an age file")]
+    Decrypt(AgeDecryptOpts),
+    #[command(about = "Generate a new X25519 age identity")]
+    Keygen(AgeKeygenOpts),
+}
+
+/// `AgeEncryptOpts` 以 age 格式加密文件，输出可由 `age -d` 解密。
+/// # 字段
+/// * `recipient` - `age1...` 公钥，可以重复指定。
+/// * `recipients_file` - 每行一个公钥的文件，`#` 开头的行为注释。
+/// * `passphrase` - 口令来源，见 `PassphraseOpts`，不能与公钥同时使用。
+/// * `work_factor` - 口令模式下 scrypt 的代价（log2 N）。
+/// * `armor` - 输出 PEM 风格的 ASCII armor。
+#[derive(Debug, Parser)]
+#[command(group(
+    ArgGroup::new("recipients")
+        .required(true)
+        .multiple(true)
+        .args(["recipient", "recipients_file", "passphrase", "passphrase_env", "passphrase_file"])
+))]
+pub struct AgeEncryptOpts {
+    #[arg(short, long, value_parser = file_check, default_value = "-")]
+    pub input: String,
+    #[arg(short, long)]
+    pub output: Option<String>,
+    #[arg(short, long, help = "age1... recipient public key, may be repeated")]
+    pub recipient: Vec<String>,
+    #[arg(short = 'R', long, value_parser = file_check, help = "File with one recipient per line")]
+    pub recipients_file: Vec<String>,
+    #[command(flatten)]
+    pub passphrase: PassphraseOpts,
+    #[arg(long, default_value_t = crate::AGE_DEFAULT_WORK_FACTOR, value_parser = clap::value_parser!(u8).range(1..=30))]
+    pub work_factor: u8,
+    #[arg(short, long, help = "Write PEM-style ASCII armor")]
+    pub armor: bool,
+}
+
+impl crate::CmdEexector for AgeEncryptOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let recipients = match self.passphrase.resolve(true)? {
+            Some(passphrase) => {
+                if !self.recipient.is_empty() || !self.recipients_file.is_empty() {
+                    anyhow::bail!("A passphrase cannot be combined with other recipients");
+                }
+                vec![crate::AgeRecipient::Scrypt {
+                    passphrase,
+                    work_factor: self.work_factor,
+                }]
+            }
+            None => crate::read_age_recipients(&self.recipient, &self.recipients_file)?,
+        };
+        crate::process_age_encrypt(&self.input, self.output.as_deref(), &recipients, self.armor)
+    }
+}
+
+/// `AgeDecryptOpts` 解密 age 文件，自动识别 ASCII armor。
+/// # 字段
+/// * `identity` - 身份文件（`rcli age keygen` 或 `age-keygen` 的输出），可以重复指定。
+/// * `passphrase` - 口令来源，用于口令加密的文件。
+/// * `max_work_factor` - 允许的最大 scrypt 代价，防止恶意文件消耗过多内存与时间。
+/// # 注意
+/// * 解密失败时会删除输出文件，输出到标准输出时已写出的部分需要丢弃。
+#[derive(Debug, Parser)]
+#[command(group(
+    ArgGroup::new("identities")
+        .required(true)
+        .multiple(true)
+        .args(["identity", "passphrase", "passphrase_env", "passphrase_file"])
+))]
+pub struct AgeDecryptOpts {
+    #[arg(short, long, value_parser = file_check, default_value = "-")]
+    pub input: String,
+    #[arg(short, long)]
+    pub output: Option<String>,
+    #[arg(long, value_parser = file_check, help = "Identity file, may be repeated")]
+    pub identity: Vec<String>,
+    #[command(flatten)]
+    pub passphrase: PassphraseOpts,
+    #[arg(long, default_value_t = crate::AGE_MAX_WORK_FACTOR)]
+    pub max_work_factor: u8,
+}
+
+impl crate::CmdEexector for AgeDecryptOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let mut identities = crate::read_age_identities(&self.identity)?;
+        if let Some(passphrase) = self.passphrase.resolve(false)? {
+            identities.push(crate::AgeIdentity::Scrypt {
+                passphrase,
+                max_work_factor: self.max_work_factor,
+            });
+        }
+        crate::process_age_decrypt(&self.input, self.output.as_deref(), &identities)
+    }
+}
+
+/// `AgeKeygenOpts` 生成新的 X25519 身份。
+/// * 身份写入 `output`（unix 下权限为 0600），默认写到标准输出；公钥同时输出到标准错误。
+#[derive(Debug, Parser)]
+pub struct AgeKeygenOpts {
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
+impl crate::CmdEexector for AgeKeygenOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let (identity, recipient) = crate::process_age_keygen()?;
+        match &self.output {
+            Some(path) => {
+                write_private(path, identity.as_bytes(), false)?;
+                eprintln!("Public key: {}", recipient);
+            }
+            None => print!("{}", identity.as_str()),
+        }
+        Ok(())
+    }
+}
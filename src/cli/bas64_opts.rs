@@ -20,13 +20,23 @@ pub struct Base64EncodeOpts {
     pub input: String,
     #[arg( long,default_value = "standard",value_parser = parse_base64_format)]
     pub format: Base64FormatType,
+    #[arg(
+        short,
+        long,
+        help = "Write encoded text to this file instead of stdout"
+    )]
+    pub output: Option<String>,
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Wrap encoded lines after this many characters (0 disables wrapping, mime defaults to 76)"
+    )]
+    pub wrap: usize,
 }
 
 impl crate::CmdEexector for Base64EncodeOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let ret = crate::process_encode(&self.input, self.format).await?;
-        println!("{}", ret);
-        Ok(())
+        crate::process_encode(&self.input, self.output.as_deref(), self.format, self.wrap).await
     }
 }
 
@@ -36,20 +46,32 @@ pub struct Base64DecodeOpts {
     pub input: String,
     #[arg( long,default_value = "standard",value_parser = parse_base64_format)]
     pub format: Base64FormatType,
+    #[arg(
+        short,
+        long,
+        help = "Write decoded bytes to this file instead of stdout"
+    )]
+    pub output: Option<String>,
+    #[arg(long, help = "Treat the decoded data as UTF-8 text")]
+    pub text: bool,
 }
 
 impl crate::CmdEexector for Base64DecodeOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let ret = crate::process_decode(&self.input, self.format).await?;
-        println!("{}", ret);
-        Ok(())
+        crate::process_decode(&self.input, self.output.as_deref(), self.format, self.text).await
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Base64FormatType {
     UrlSafe,
     STANDARD,
+    UrlSafeNoPad,
+    StandardNoPad,
+    /// RFC 2045：标准字母表，每 76 个字符以 CRLF 换行
+    Mime,
+    /// 仅用于解码：忽略空白，自动识别字母表与填充
+    Lenient,
 }
 
 impl FromStr for Base64FormatType {
@@ -59,6 +81,10 @@ impl FromStr for Base64FormatType {
         match s.to_lowercase().as_str() {
             "urlsafe" => Ok(Base64FormatType::UrlSafe),
             "standard" => Ok(Base64FormatType::STANDARD),
+            "urlsafe-nopad" => Ok(Base64FormatType::UrlSafeNoPad),
+            "standard-nopad" => Ok(Base64FormatType::StandardNoPad),
+            "mime" => Ok(Base64FormatType::Mime),
+            "lenient" => Ok(Base64FormatType::Lenient),
             v => anyhow::bail!("Unsupported base64 format: {}", v),
         }
     }
@@ -69,6 +95,10 @@ impl fmt::Display for Base64FormatType {
         match self {
             Base64FormatType::UrlSafe => write!(f, "urlsafe"),
             Base64FormatType::STANDARD => write!(f, "standard"),
+            Base64FormatType::UrlSafeNoPad => write!(f, "urlsafe-nopad"),
+            Base64FormatType::StandardNoPad => write!(f, "standard-nopad"),
+            Base64FormatType::Mime => write!(f, "mime"),
+            Base64FormatType::Lenient => write!(f, "lenient"),
         }
     }
 }
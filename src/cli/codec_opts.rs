@@ -0,0 +1,140 @@
+use core::fmt;
+use std::str::FromStr;
+
+use clap::Parser;
+
+use super::file_check;
+
+/// `CodecCmd` 是 base64 以外的文本编码子命令，与 `Base64Cmd` 的用法相同。
+/// * `Encode` - 把任意二进制数据编码为文本。
+/// * `Decode` - 把文本解码回原始字节。
+/// 支持的格式见 `CodecFormat`：`hex`、`hex-upper`、`base32`、`base32-crockford`、`base58`、
+/// `ascii85`、`z85` 与 `percent`。
+/// 顶层的 `rcli encode` / `rcli decode` 与 `rcli codec encode` / `rcli codec decode` 等价。
+#[derive(Debug, Parser)]
+#[enum_dispatch::enum_dispatch(CmdEexector)]
+pub enum CodecCmd {
+    #[command(
+        name = "encode",
+        about = "Encode as hex, base32, base58, ascii85, z85 or percent-encoding"
+    )]
+    Encode(CodecEncodeOpts),
+    #[command(
+        name = "decode",
+        about = "Decode hex, base32, base58, ascii85, z85 or percent-encoding"
+    )]
+    Decode(CodecDecodeOpts),
+}
+
+/// `CodecEncodeOpts` 是一个用于保存编码选项的结构体。
+/// # 字段
+/// * `input` - 要编码的输入文件，按二进制读取。默认值为 `-`（标准输入）。
+/// * `output` - 输出文件，默认写到标准输出，编码结果以换行结尾。
+/// * `format` - 编码格式。`z85` 要求输入长度是 4 的倍数。
+/// # 注意
+/// * 输入按 60 KiB 分块流式编码，内存占用与输入大小无关；`base58` 是整体的大数转换，
+///   会把输入整个读入内存。
+#[derive(Debug, Parser)]
+pub struct CodecEncodeOpts {
+    #[arg(short, long, value_parser = file_check, default_value = "-")]
+    pub input: String,
+    #[arg(
+        short,
+        long,
+        help = "Write encoded text to this file instead of stdout"
+    )]
+    pub output: Option<String>,
+    #[arg(long, value_parser = parse_codec_format)]
+    pub format: CodecFormat,
+}
+
+impl crate::CmdEexector for CodecEncodeOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        crate::process_codec_encode(&self.input, self.output.as_deref(), self.format).await
+    }
+}
+
+/// `CodecDecodeOpts` 是一个用于保存解码选项的结构体。
+/// # 字段
+/// * `input` - 要解码的文本文件。默认值为 `-`（标准输入）。
+/// * `output` - 输出文件，默认把解码后的字节原样写到标准输出。
+/// * `format` - 编码格式，`hex` 与 `hex-upper` 都接受大小写混合的输入，`base32` 不区分大小写。
+/// * `text` - 把解码结果当作 UTF-8 文本输出（末尾加换行），不是合法的 UTF-8 时报错。
+/// # 注意
+/// * 解码前会忽略空白字符（换行等），百分号编码只去掉首尾空白。
+/// * `ascii85` 的输入可以带 `<~` / `~>` 定界符。
+/// * `hex`、`base32`、`z85` 与 `percent` 流式解码，输入有误时已解码的部分可能已经写出；
+///   `base58` 与 `ascii85` 会把输入整个读入内存。
+#[derive(Debug, Parser)]
+pub struct CodecDecodeOpts {
+    #[arg(short, long, value_parser = file_check, default_value = "-")]
+    pub input: String,
+    #[arg(
+        short,
+        long,
+        help = "Write decoded bytes to this file instead of stdout"
+    )]
+    pub output: Option<String>,
+    #[arg(long, value_parser = parse_codec_format)]
+    pub format: CodecFormat,
+    #[arg(long, help = "Treat the decoded data as UTF-8 text")]
+    pub text: bool,
+}
+
+impl crate::CmdEexector for CodecDecodeOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        crate::process_codec_decode(&self.input, self.output.as_deref(), self.format, self.text)
+            .await
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodecFormat {
+    Hex,
+    HexUpper,
+    Base32,
+    Base32Crockford,
+    /// Bitcoin 字母表
+    Base58,
+    Ascii85,
+    Z85,
+    /// URL 百分号编码（RFC 3986）
+    Percent,
+}
+
+impl FromStr for CodecFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "hex" => Ok(CodecFormat::Hex),
+            "hex-upper" => Ok(CodecFormat::HexUpper),
+            "base32" => Ok(CodecFormat::Base32),
+            "base32-crockford" => Ok(CodecFormat::Base32Crockford),
+            "base58" => Ok(CodecFormat::Base58),
+            "ascii85" => Ok(CodecFormat::Ascii85),
+            "z85" => Ok(CodecFormat::Z85),
+            "percent" => Ok(CodecFormat::Percent),
+            v => anyhow::bail!("Unsupported encoding format: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for CodecFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CodecFormat::Hex => write!(f, "hex"),
+            CodecFormat::HexUpper => write!(f, "hex-upper"),
+            CodecFormat::Base32 => write!(f, "base32"),
+            CodecFormat::Base32Crockford => write!(f, "base32-crockford"),
+            CodecFormat::Base58 => write!(f, "base58"),
+            CodecFormat::Ascii85 => write!(f, "ascii85"),
+            CodecFormat::Z85 => write!(f, "z85"),
+            CodecFormat::Percent => write!(f, "percent"),
+        }
+    }
+}
+
+fn parse_codec_format(s: &str) -> Result<CodecFormat, anyhow::Error> {
+    s.parse()
+}
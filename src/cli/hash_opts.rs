@@ -0,0 +1,137 @@
+use core::fmt;
+use std::str::FromStr;
+
+use clap::Parser;
+
+use super::file_check;
+
+/// `HashOpts` 计算文件摘要，输出与 `sha256sum` 兼容。
+/// * 多个文件会并行计算。
+/// * `--check` 模式下，`files` 为清单文件，逐行校验其中列出的文件；有文件不匹配或无法读取时
+///   以退出码 3 退出，`--quiet` 时不输出任何内容。
+#[derive(Debug, Parser)]
+pub struct HashOpts {
+    #[arg(value_parser = file_check, default_value = "-")]
+    pub files: Vec<String>,
+    #[arg(short, long, default_value = "blake3", value_parser = parse_hash_algorithm)]
+    pub algorithm: HashAlgorithm,
+    #[arg(short, long, help = "Read checksums from the files and verify them")]
+    pub check: bool,
+    #[arg(
+        short,
+        long,
+        requires = "check",
+        help = "Print nothing in --check mode, report the result only through the exit code"
+    )]
+    pub quiet: bool,
+}
+
+impl crate::CmdEexector for HashOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        if !self.check {
+            for (digest, file) in crate::process_hash(&self.files, self.algorithm)? {
+                println!("{}  {}", digest, file);
+            }
+            return Ok(());
+        }
+
+        let ret = crate::process_hash_check(&self.files, self.algorithm)?;
+        let mut failed = 0;
+        let mut unreadable = 0;
+        for (file, result) in ret.iter() {
+            if self.quiet {
+                match result {
+                    Some(true) => {}
+                    Some(false) => failed += 1,
+                    None => unreadable += 1,
+                }
+                continue;
+            }
+            match result {
+                Some(true) => println!("{}: OK", file),
+                Some(false) => {
+                    failed += 1;
+                    println!("{}: FAILED", file);
+                }
+                None => {
+                    unreadable += 1;
+                    println!("{}: FAILED open or read", file);
+                }
+            }
+        }
+        if self.quiet && failed + unreadable > 0 {
+            return Err(crate::VerificationFailed::reported().into());
+        }
+        if unreadable > 0 {
+            eprintln!("WARNING: {} listed file(s) could not be read", unreadable);
+        }
+        if failed > 0 {
+            eprintln!("WARNING: {} computed checksum(s) did NOT match", failed);
+        }
+        if failed + unreadable > 0 {
+            return Err(crate::VerificationFailed::new("Checksum verification failed").into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Blake3,
+    Sha1,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+    Sha3_224,
+    Sha3_256,
+    Sha3_384,
+    Sha3_512,
+    Md5,
+    Crc32,
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "blake3" => Ok(HashAlgorithm::Blake3),
+            "sha1" => Ok(HashAlgorithm::Sha1),
+            "sha224" => Ok(HashAlgorithm::Sha224),
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            "sha384" => Ok(HashAlgorithm::Sha384),
+            "sha512" => Ok(HashAlgorithm::Sha512),
+            "sha3-224" => Ok(HashAlgorithm::Sha3_224),
+            "sha3-256" => Ok(HashAlgorithm::Sha3_256),
+            "sha3-384" => Ok(HashAlgorithm::Sha3_384),
+            "sha3-512" => Ok(HashAlgorithm::Sha3_512),
+            "md5" => Ok(HashAlgorithm::Md5),
+            "crc32" => Ok(HashAlgorithm::Crc32),
+            v => anyhow::bail!("Unsupported hash algorithm: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HashAlgorithm::Blake3 => write!(f, "blake3"),
+            HashAlgorithm::Sha1 => write!(f, "sha1"),
+            HashAlgorithm::Sha224 => write!(f, "sha224"),
+            HashAlgorithm::Sha256 => write!(f, "sha256"),
+            HashAlgorithm::Sha384 => write!(f, "sha384"),
+            HashAlgorithm::Sha512 => write!(f, "sha512"),
+            HashAlgorithm::Sha3_224 => write!(f, "sha3-224"),
+            HashAlgorithm::Sha3_256 => write!(f, "sha3-256"),
+            HashAlgorithm::Sha3_384 => write!(f, "sha3-384"),
+            HashAlgorithm::Sha3_512 => write!(f, "sha3-512"),
+            HashAlgorithm::Md5 => write!(f, "md5"),
+            HashAlgorithm::Crc32 => write!(f, "crc32"),
+        }
+    }
+}
+
+fn parse_hash_algorithm(s: &str) -> Result<HashAlgorithm, anyhow::Error> {
+    s.parse()
+}
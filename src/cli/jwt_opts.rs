@@ -38,13 +38,24 @@ pub struct JWTVerifyOpts {
     pub secret: String,
     #[arg(long, default_value = "test", help = "example: test,test1,test2")]
     pub aud: String,
+    #[arg(
+        short,
+        long,
+        help = "Print nothing, report the result only through the exit code"
+    )]
+    pub quiet: bool,
 }
 
 impl crate::CmdEexector for JWTVerifyOpts {
     async fn execute(self) -> anyhow::Result<()> {
         let aud = self.aud.split(',').map(|x| x.to_string()).collect();
-        let ret = crate::process_verify_jwt_token(&self.token, &self.secret, aud).await?;
-        println!("{}", ret);
+        let ret = crate::quiet_verification(
+            crate::process_verify_jwt_token(&self.token, &self.secret, aud).await,
+            self.quiet,
+        )?;
+        if !self.quiet {
+            println!("{}", ret);
+        }
         Ok(())
     }
 }
@@ -0,0 +1,174 @@
+use clap::{ArgGroup, Parser};
+
+use super::{
+    file_check,
+    text::{parse_format, parse_key_encoding, KeyEncoding, TextSignFormat},
+    write_private, PassphraseOpts,
+};
+
+/// `KeyCmd` 管理本地密钥环中的命名密钥，`text sign` / `text verify` 可以用 `--key-id` 引用。
+/// 密钥环位于数据目录下的 `rcli/keyring`（Linux 为 `~/.local/share/rcli/keyring`），
+/// 可以用 `RCLI_KEYRING_DIR` 环境变量指定其他目录。
+/// * `List` - 列出所有密钥。
+/// * `Add` - 导入已有的密钥文件或生成新密钥。
+/// * `Remove` - 删除密钥。
+/// * `Export` - 导出公钥或私钥。
+/// * `Rename` - 重命名密钥。
+#[derive(Debug, Parser)]
+#[enum_dispatch::enum_dispatch(CmdEexector)]
+pub enum KeyCmd {
+    #[command(about = "List keys in the keyring")]
+    List(KeyListOpts),
+    #[command(about = "Import key files or generate a new key")]
+    Add(KeyAddOpts),
+    #[command(about = "Remove a key from the keyring")]
+    Remove(KeyRemoveOpts),
+    #[command(about = "Export the public or private part of a key")]
+    Export(KeyExportOpts),
+    #[command(about = "Rename a key")]
+    Rename(KeyRenameOpts),
+}
+
+/// `KeyListOpts` 每行输出一个密钥：id、算法、包含的部分，以及私钥是否用口令加密。
+#[derive(Debug, Parser)]
+pub struct KeyListOpts {}
+
+impl crate::CmdEexector for KeyListOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let keyring = crate::Keyring::open()?;
+        let entries = keyring.list()?;
+        if entries.is_empty() {
+            eprintln!("No keys in {}", keyring.root().display());
+        }
+        for entry in entries {
+            let parts = entry
+                .kinds
+                .iter()
+                .map(|kind| kind.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let encrypted = if entry.encrypted { " (encrypted)" } else { "" };
+            println!(
+                "{:<24} {:<11} {}{}",
+                entry.id, entry.format, parts, encrypted
+            );
+        }
+        Ok(())
+    }
+}
+
+/// `KeyAddOpts` 把密钥加入密钥环。
+/// # 字段
+/// * `id` - 密钥名，只能包含字母、数字、`.`、`_` 与 `-`。
+/// * `key` - 要导入的密钥文件，可以重复指定（例如 ed25519 的私钥与公钥），支持 `text generate`
+///   的所有编码以及 PKCS#8 / OpenSSH；只导入 ed25519 私钥时会自动保存对应的公钥。
+/// * `generate` - 生成指定算法的新密钥（blake3、ed25519、base64、hmac-sha256/384/512 或 x25519），
+///   不能与 `key` 同时使用。
+/// * `passphrase` - 生成新密钥时用口令加密私钥，见 `PassphraseOpts`。
+/// # 示例
+/// ```shell
+/// $ rcli key add release-2026 --generate ed25519 --passphrase
+/// $ rcli key add ci --key keys/ed25519.priv --key keys/ed25519.pub
+/// $ rcli text sign --key-id release-2026 -i README.md
+/// ```
+/// # 注意
+/// * 导入的文件按原样保存，加密的私钥保持加密，导入时会要求输入口令以校验文件。
+#[derive(Debug, Parser)]
+#[command(group(
+    ArgGroup::new("source")
+        .required(true)
+        .args(["key", "generate"])
+))]
+pub struct KeyAddOpts {
+    pub id: String,
+    #[arg(long, value_parser = file_check, help = "Key file to import, may be repeated")]
+    pub key: Vec<String>,
+    #[arg(long, value_name = "FORMAT", value_parser = parse_format, help = "Generate a new blake3, ed25519, base64, hmac-sha256/384/512 or x25519 key")]
+    pub generate: Option<TextSignFormat>,
+    #[command(flatten)]
+    pub passphrase: PassphraseOpts,
+}
+
+impl crate::CmdEexector for KeyAddOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let keyring = crate::Keyring::open()?;
+        let entry = match self.generate {
+            Some(format) => {
+                let passphrase = self.passphrase.resolve(true)?;
+                keyring.generate(&self.id, format, passphrase.as_deref().map(|p| p.as_str()))?
+            }
+            None => {
+                if self.passphrase.resolve(false)?.is_some() {
+                    anyhow::bail!(
+                        "--passphrase only applies to --generate, imported files are stored as is"
+                    );
+                }
+                keyring.import(&self.id, &self.key)?
+            }
+        };
+        eprintln!("Added {} key '{}'", entry.format, entry.id);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct KeyRemoveOpts {
+    pub id: String,
+}
+
+impl crate::CmdEexector for KeyRemoveOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        crate::Keyring::open()?.remove(&self.id)
+    }
+}
+
+/// `KeyExportOpts` 导出密钥。
+/// # 字段
+/// * `private` - 导出私钥（或 blake3 / chacha20 的对称密钥），必须指定 `output`，文件权限为 0600。
+///   不指定 `encoding` 时按原样导出，加密的私钥保持加密。
+/// * `encoding` - 输出编码，公钥默认为 `pem`。
+/// * `output` - 输出文件，导出公钥时默认写到标准输出。
+#[derive(Debug, Parser)]
+pub struct KeyExportOpts {
+    pub id: String,
+    #[arg(
+        long,
+        help = "Export the private or symmetric key instead of the public key"
+    )]
+    pub private: bool,
+    #[arg(long, value_parser = parse_key_encoding)]
+    pub encoding: Option<KeyEncoding>,
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
+impl crate::CmdEexector for KeyExportOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let keyring = crate::Keyring::open()?;
+        if self.private {
+            let Some(output) = &self.output else {
+                anyhow::bail!("Exporting a private key needs --output");
+            };
+            let data = keyring.export_private(&self.id, self.encoding)?;
+            return write_private(output, &data, false);
+        }
+        let data = keyring.export_public(&self.id, self.encoding.unwrap_or(KeyEncoding::Pem))?;
+        match &self.output {
+            Some(output) => std::fs::write(output, data)?,
+            None => std::io::Write::write_all(&mut std::io::stdout(), &data)?,
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct KeyRenameOpts {
+    pub from: String,
+    pub to: String,
+}
+
+impl crate::CmdEexector for KeyRenameOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        crate::Keyring::open()?.rename(&self.from, &self.to)
+    }
+}
@@ -1,22 +1,42 @@
+pub mod age_opts;
 pub mod bas64_opts;
+pub mod codec_opts;
 pub mod csv_opts;
 pub mod gen_pass_opts;
+pub mod hash_opts;
 pub mod http;
 pub mod jwt_opts;
+pub mod key_opts;
+pub mod otp_opts;
 pub mod text;
 
-use std::path::{Path, PathBuf};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
 
-use clap::{command, Parser, Subcommand};
+use clap::{ArgGroup, Args, Parser, Subcommand};
 use enum_dispatch::enum_dispatch;
+use zeroize::Zeroizing;
 
-pub use self::{bas64_opts::*, csv_opts::*, gen_pass_opts::*, http::*, jwt_opts::*, text::*};
+pub use self::{
+    age_opts::*, bas64_opts::*, codec_opts::*, csv_opts::*, gen_pass_opts::*, hash_opts::*,
+    http::*, jwt_opts::*, key_opts::*, otp_opts::*, text::*,
+};
 
 #[derive(Debug, Parser)]
 #[command(name = "rcli", version, author, about = "use csv2json,generate password,encode or decode base64 tools by this cli ",long_about = None)]
 pub struct Opts {
     #[command(subcommand)]
     pub cmd: Command,
+    /// 接受旧版本生成的无类型密钥文件（已弃用），无法确认密钥与 `--format` 是否一致。
+    #[arg(
+        long,
+        global = true,
+        help = "Accept untyped key files written by old rcli versions (deprecated)"
+    )]
+    pub legacy_key: bool,
 }
 
 #[derive(Debug, Subcommand)]
@@ -31,6 +51,30 @@ pub enum Command {
     #[command(subcommand, about = "Base64 encode or decode")]
     Base64(Base64Cmd),
 
+    #[command(
+        subcommand,
+        about = "Encode or decode hex, base32, base58, ascii85, z85 or percent-encoding"
+    )]
+    Codec(CodecCmd),
+
+    #[command(
+        name = "encode",
+        about = "Encode as hex, base32, base58, ascii85, z85 or percent-encoding (same as codec encode)"
+    )]
+    Encode(CodecEncodeOpts),
+
+    #[command(
+        name = "decode",
+        about = "Decode hex, base32, base58, ascii85, z85 or percent-encoding (same as codec decode)"
+    )]
+    Decode(CodecDecodeOpts),
+
+    #[command(name = "hash", about = "Compute or check file checksums")]
+    Hash(HashOpts),
+
+    #[command(subcommand, about = "Manage named keys in the local keyring")]
+    Key(KeyCmd),
+
     #[command(subcommand, about = "Text sign or verify")]
     Text(TextSubCmd),
 
@@ -39,6 +83,15 @@ pub enum Command {
 
     #[command(subcommand, about = "JWT token")]
     JWT(JWTCmd),
+
+    #[command(subcommand, about = "TOTP / HOTP one-time passwords")]
+    Otp(OtpCmd),
+
+    #[command(
+        subcommand,
+        about = "age encryption (X25519 recipients / scrypt passphrase)"
+    )]
+    Age(AgeCmd),
 }
 
 fn file_check(fliename: &str) -> Result<String, anyhow::Error> {
@@ -64,10 +117,94 @@ fn path_buf_check(path: &str) -> Result<PathBuf, anyhow::Error> {
     }
 }
 
+/// 以 0600 权限写入私钥文件（非 unix 平台使用默认权限），
+/// `overwrite` 为 false 时拒绝覆盖已有文件。
+pub(crate) fn write_private(path: &str, data: &[u8], overwrite: bool) -> anyhow::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true);
+    if overwrite {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    // 已存在的文件不会应用 mode，需要单独设置
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(data)?;
+    Ok(())
+}
+
+/// `PassphraseOpts` 是可选的口令来源，三者至多指定一个，都不指定时不使用口令。
+/// * `passphrase` - 在终端提示输入口令。
+/// * `passphrase_env` - 从指定的环境变量读取口令。
+/// * `passphrase_file` - 从文件读取口令，末尾的换行会被去掉。
+#[derive(Debug, Args)]
+#[command(group(
+    ArgGroup::new("passphrase_source")
+        .multiple(false)
+        .args(["passphrase", "passphrase_env", "passphrase_file"])
+))]
+pub struct PassphraseOpts {
+    #[arg(long, help = "Prompt for a passphrase on the terminal")]
+    pub passphrase: bool,
+    #[arg(
+        long,
+        value_name = "VAR",
+        help = "Read the passphrase from an environment variable"
+    )]
+    pub passphrase_env: Option<String>,
+    #[arg(long, value_name = "FILE", value_parser = file_check, help = "Read the passphrase from a file")]
+    pub passphrase_file: Option<String>,
+}
+
+impl PassphraseOpts {
+    pub(crate) fn resolve(&self, confirm: bool) -> anyhow::Result<Option<Zeroizing<String>>> {
+        if !self.passphrase && self.passphrase_env.is_none() && self.passphrase_file.is_none() {
+            return Ok(None);
+        }
+        crate::read_passphrase(
+            self.passphrase_env.as_deref(),
+            self.passphrase_file.as_deref(),
+            confirm,
+        )
+        .map(Some)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_usage_exit_code() {
+        use clap::CommandFactory;
+        let err = Opts::command()
+            .try_get_matches_from(["rcli", "text", "verify"])
+            .unwrap_err();
+        assert_eq!(err.exit_code(), crate::EXIT_USAGE as i32);
+    }
+
+    #[test]
+    fn test_codec_subcommand() {
+        let opts =
+            Opts::try_parse_from(["rcli", "codec", "decode", "--format", "z85", "--text"]).unwrap();
+        match opts.cmd {
+            Command::Codec(CodecCmd::Decode(opts)) => {
+                assert_eq!(opts.format, CodecFormat::Z85);
+                assert!(opts.text);
+            }
+            cmd => panic!("unexpected command {:?}", cmd),
+        }
+        let opts = Opts::try_parse_from(["rcli", "encode", "--format", "hex"]).unwrap();
+        match opts.cmd {
+            Command::Encode(opts) => assert_eq!(opts.format, CodecFormat::Hex),
+            cmd => panic!("unexpected command {:?}", cmd),
+        }
+    }
+
     #[test]
     fn test_file_check() {
         assert!(file_check("-").is_ok());
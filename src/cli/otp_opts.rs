@@ -0,0 +1,259 @@
+use core::fmt;
+use std::{path::Path, str::FromStr};
+
+use anyhow::Context;
+use clap::Parser;
+use tokio::fs;
+
+use super::{file_check, write_private};
+
+/// `OtpCmd` 是一次性密码（TOTP / HOTP）相关的子命令。
+/// * `Generate` - 根据 base32 密钥生成一次性密码。
+/// * `Verify` - 在允许的漂移窗口内验证一次性密码。
+/// * `New` - 生成新的密钥与 `otpauth://` URI。
+#[derive(Debug, Parser)]
+#[enum_dispatch::enum_dispatch(CmdEexector)]
+pub enum OtpCmd {
+    #[command(about = "Generate a TOTP / HOTP code")]
+    Generate(OtpGenerateOpts),
+    #[command(about = "Verify a TOTP / HOTP code")]
+    Verify(OtpVerifyOpts),
+    #[command(about = "Generate a new OTP secret and otpauth:// URI")]
+    New(OtpNewOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpGenerateOpts {
+    #[arg(long, help = "Base32 encoded secret")]
+    pub secret: Option<String>,
+    #[arg(long, value_parser = file_check, conflicts_with = "secret", help = "File containing the base32 secret")]
+    pub key: Option<String>,
+    #[arg(long, default_value = "sha1", value_parser = parse_otp_algorithm)]
+    pub algorithm: OtpAlgorithm,
+    #[arg(long, default_value_t = 6)]
+    pub digits: u32,
+    #[arg(long, default_value_t = 30)]
+    pub period: u64,
+    #[arg(long, help = "Use HOTP with this counter instead of TOTP")]
+    pub counter: Option<u64>,
+    #[arg(long, help = "Unix time used instead of the current time")]
+    pub timestamp: Option<u64>,
+}
+
+impl crate::CmdEexector for OtpGenerateOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let secret = crate::read_otp_secret(self.secret, self.key)?;
+        let otp = crate::Otp::try_new(&secret, self.algorithm, self.digits, self.period)?;
+        let ret = crate::process_otp_generate(&otp, self.counter, self.timestamp)?;
+        println!("{}", ret);
+        Ok(())
+    }
+}
+
+/// 漂移窗口的上限：每个窗口值都会多计算一次 HMAC 并多接受一个候选码，
+/// 过大的窗口会让任意验证码几乎都能通过。
+const MAX_WINDOW: u64 = 100;
+
+/// `OtpVerifyOpts` 在漂移窗口内验证一次性密码。
+/// * TOTP 输出 `true` / `false`。
+/// * HOTP（指定 `--counter`）验证通过时额外输出下一个计数器（匹配值加一），调用方应保存该值。
+/// * `window` 不能超过 100。
+#[derive(Debug, Parser)]
+pub struct OtpVerifyOpts {
+    #[arg(long, help = "Base32 encoded secret")]
+    pub secret: Option<String>,
+    #[arg(long, value_parser = file_check, conflicts_with = "secret", help = "File containing the base32 secret")]
+    pub key: Option<String>,
+    #[arg(short, long)]
+    pub code: String,
+    #[arg(long, default_value = "sha1", value_parser = parse_otp_algorithm)]
+    pub algorithm: OtpAlgorithm,
+    #[arg(long, default_value_t = 6)]
+    pub digits: u32,
+    #[arg(long, default_value_t = 30)]
+    pub period: u64,
+    #[arg(long, help = "Use HOTP with this counter instead of TOTP")]
+    pub counter: Option<u64>,
+    #[arg(long, help = "Unix time used instead of the current time")]
+    pub timestamp: Option<u64>,
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u64).range(0..=MAX_WINDOW),
+        help = "Allowed drift in time steps (TOTP) or look-ahead counters (HOTP)"
+    )]
+    pub window: u64,
+    #[arg(
+        short,
+        long,
+        help = "Print nothing, report the result only through the exit code"
+    )]
+    pub quiet: bool,
+}
+
+impl crate::CmdEexector for OtpVerifyOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let secret = crate::read_otp_secret(self.secret, self.key)?;
+        let otp = crate::Otp::try_new(&secret, self.algorithm, self.digits, self.period)?;
+        let ret =
+            crate::process_otp_verify(&otp, &self.code, self.counter, self.timestamp, self.window)?;
+        if !self.quiet {
+            println!("{}", ret.is_some());
+            // HOTP 需要把计数器推进到匹配值之后，否则向前查找的窗口无法重新同步
+            if let (Some(_), Some(matched)) = (self.counter, ret) {
+                println!("next counter: {}", matched + 1);
+            }
+        }
+        if ret.is_none() {
+            return Err(crate::VerificationFailed::reported().into());
+        }
+        Ok(())
+    }
+}
+
+/// `OtpNewOpts` 生成新的 OTP 密钥。
+/// * 密钥以 base32 保存到 `path` 目录下的 `otp.key`，目录不存在时会自动创建。
+/// * `otp.key` 的权限为 0600（unix），已存在时报错，不会覆盖原有的密钥。
+/// * 同时输出密钥与可导入认证器的 `otpauth://` URI。
+#[derive(Debug, Parser)]
+pub struct OtpNewOpts {
+    #[arg(long)]
+    pub issuer: String,
+    #[arg(long)]
+    pub account: String,
+    #[arg(long, default_value = "sha1", value_parser = parse_otp_algorithm)]
+    pub algorithm: OtpAlgorithm,
+    #[arg(long, default_value_t = 6)]
+    pub digits: u32,
+    #[arg(long, default_value_t = 30)]
+    pub period: u64,
+    #[arg(long, help = "Create an HOTP secret starting at this counter")]
+    pub counter: Option<u64>,
+    #[arg(long, default_value = "keys")]
+    pub path: String,
+}
+
+impl crate::CmdEexector for OtpNewOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let (secret, uri) = crate::process_otp_new(
+            &self.issuer,
+            &self.account,
+            self.algorithm,
+            self.digits,
+            self.period,
+            self.counter,
+        )?;
+        if !Path::new(&self.path).exists() {
+            fs::create_dir_all(&self.path).await?;
+        }
+        let key_path = format!("{}/otp.key", self.path);
+        write_private(&key_path, secret.as_bytes(), false)
+            .with_context(|| format!("Failed to write {}, the file must not exist", key_path))?;
+        println!("{}", secret);
+        println!("{}", uri);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum OtpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl OtpAlgorithm {
+    /// RFC 4226 / 6238 建议的密钥长度（与哈希输出长度一致）。
+    pub fn key_len(&self) -> usize {
+        match self {
+            OtpAlgorithm::Sha1 => 20,
+            OtpAlgorithm::Sha256 => 32,
+            OtpAlgorithm::Sha512 => 64,
+        }
+    }
+}
+
+impl FromStr for OtpAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sha1" => Ok(OtpAlgorithm::Sha1),
+            "sha256" => Ok(OtpAlgorithm::Sha256),
+            "sha512" => Ok(OtpAlgorithm::Sha512),
+            v => anyhow::bail!("Unsupported OTP algorithm: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for OtpAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OtpAlgorithm::Sha1 => write!(f, "sha1"),
+            OtpAlgorithm::Sha256 => write!(f, "sha256"),
+            OtpAlgorithm::Sha512 => write!(f, "sha512"),
+        }
+    }
+}
+
+fn parse_otp_algorithm(s: &str) -> Result<OtpAlgorithm, anyhow::Error> {
+    s.parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CmdEexector;
+
+    #[test]
+    fn test_verify_window_is_bounded() {
+        let parse = |window: &str| {
+            crate::Opts::try_parse_from([
+                "rcli",
+                "otp",
+                "verify",
+                "--secret",
+                "GEZDGNBV",
+                "--code",
+                "123456",
+                "--counter",
+                "0",
+                "--window",
+                window,
+            ])
+        };
+        assert!(parse("100").is_ok());
+        assert!(parse("101").is_err());
+        assert!(parse("1000000").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_new_secret_is_private() -> anyhow::Result<()> {
+        let dir = std::env::temp_dir().join(format!("rcli-otp-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let opts = || OtpNewOpts {
+            issuer: "rcli".to_string(),
+            account: "alice@example.com".to_string(),
+            algorithm: OtpAlgorithm::Sha1,
+            digits: 6,
+            period: 30,
+            counter: None,
+            path: dir.to_str().unwrap().to_string(),
+        };
+        opts().execute().await?;
+        let key = dir.join("otp.key");
+        let secret = std::fs::read_to_string(&key)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&key)?.permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // 已有的密钥不会被覆盖
+        assert!(opts().execute().await.is_err());
+        assert_eq!(std::fs::read_to_string(&key)?, secret);
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
@@ -1,13 +1,16 @@
 use core::fmt;
 use std::{path::Path, str::FromStr};
 
-use clap::Parser;
+use anyhow::Context;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use clap::{ArgGroup, Args, Parser};
 use tokio::{
     fs::{self, File},
     io::AsyncWriteExt,
 };
+use zeroize::Zeroizing;
 
-use super::{file_check, path_check};
+use super::{file_check, path_check, write_private, PassphraseOpts};
 
 /// `TextSubCmd` 是一个用于保存文本文件子命令的枚举。
 /// 它使用 `enum_dispatch` 宏来实现 `CmdEexector` trait。
@@ -15,6 +18,11 @@ use super::{file_check, path_check};
 /// * `Sign` - 用于签名文本文件。
 /// * `Verify` - 用于验证文本文件。
 /// * `Generate` - 用于生成新的密钥。
+/// * `Encrypt` - 使用 ChaCha20-Poly1305 加密文件，密钥来自密钥文件或口令。
+/// * `Decrypt` - 解密 `Encrypt` 生成的文件。
+/// * `SignDir` - 为目录生成 blake3 清单并用 Ed25519 签名。
+/// * `VerifyDir` - 验证清单签名并报告新增、缺失与被修改的文件。
+/// * `Derive` - 用 X25519 密钥协商派生 `Encrypt` / `Decrypt` 使用的密钥文件。
 #[derive(Debug, Parser)]
 #[enum_dispatch::enum_dispatch(CmdEexector)]
 pub enum TextSubCmd {
@@ -24,6 +32,16 @@ pub enum TextSubCmd {
     Verify(TextVerifyOpts),
     #[command(about = "Generate a new key")]
     Generate(TextKeyGenerateOpts),
+    #[command(about = "Encrypt text with a ChaCha20-Poly1305 key or a passphrase")]
+    Encrypt(TextEncryptOpts),
+    #[command(about = "Decrypt text encrypted by `text encrypt`")]
+    Decrypt(TextDecryptOpts),
+    #[command(about = "Hash every file in a directory and sign the manifest with ed25519")]
+    SignDir(TextSignDirOpts),
+    #[command(about = "Verify a directory against its signed manifest")]
+    VerifyDir(TextVerifyDirOpts),
+    #[command(about = "Derive a ChaCha20-Poly1305 key from an x25519 key and a peer public key")]
+    Derive(TextDeriveOpts),
 }
 
 /// `TextSignOpts` 是一个用于保存签名文本文件选项的结构体。
@@ -32,21 +50,91 @@ pub enum TextSubCmd {
 ///
 /// * `input` - 要签名的输入文件。它接受一个短或长参数，并使用 `file_check` 进行验证。默认值为 `-`。
 /// * `key` - 用于签名的密钥文件。它接受一个长参数，并使用 `file_check` 进行验证。
-/// * `format` - 文本签名的格式。它接受一个长参数，并使用 `parse_format` 进行验证。默认值为 `blake3`。
+/// * `key_id` - 密钥环中的密钥名（见 `rcli key`），算法与密钥文件都取自密钥环，不能与 `key` 同时使用。
+/// * `format` - 文本签名的格式。它接受一个长参数，并使用 `parse_format` 进行验证。使用 `key` 时默认值为 `blake3`，
+///   使用 `key_id` 时取密钥环中记录的算法，显式指定且不一致时报错。
+/// * `output` - 可选，把签名连同算法、密钥指纹与时间戳写入签名文件（例如 `dist/app.tar.gz.sig`），
+///   不指定时直接输出 URL-safe base64 签名。
+/// * `trusted_comment` - 仅用于 minisign，写入 `.minisig` 并参与签名的可信注释，
+///   默认记录时间戳与文件名。
+/// * `encoding` - 直接输出的签名的编码：`base64`（默认，URL-safe 无填充）或 `hex`。
+///   webhook 通常在请求头中发送十六进制的 HMAC，例如：
+/// ```shell
+/// $ rcli text sign --format hmac-sha256 --key webhook.secret --encoding hex -i payload.json
+/// ```
+/// * `ecdsa_encoding` - ECDSA 签名的形式：`raw`（默认，64 字节的 r || s）或 `der`（与 `openssl dgst -sign` 相同）。
+///
+/// `--format ecdsa-p256`、`ecdsa-secp256k1` 与 `rsa-pss` 使用 PKCS#8 私钥（也接受 openssl 生成的 SEC1 /
+/// PKCS#1 私钥），先对输入做 SHA-256 再签名，例如：
+/// ```shell
+/// $ rcli text sign --format ecdsa-p256 --key keys/ecdsa-p256.priv --ecdsa-encoding der -i README.md
+/// ```
+///
+/// `--format minisign` 使用 minisign 私钥以预哈希模式签名，输出（或写入 `output`）`.minisig` 文件内容。
+///
+/// 私钥用口令加密时会在终端提示输入口令，设置 `RCLI_KEY_PASSPHRASE` 环境变量可以跳过提示。
 #[derive(Debug, Parser)]
+#[command(group(ArgGroup::new("signing_key").required(true).args(["key", "key_id"])))]
 pub struct TextSignOpts {
     #[arg(short, long, value_parser = file_check,default_value = "-")]
     pub input: String,
     #[arg( long,value_parser = file_check)]
-    pub key: String,
-    #[arg(long,default_value = "blake3",value_parser =  parse_format)]
-    pub format: TextSignFormat,
+    pub key: Option<String>,
+    #[arg(long, help = "Name of a key in the keyring (see `rcli key`)")]
+    pub key_id: Option<String>,
+    #[arg(long, value_parser = parse_format)]
+    pub format: Option<TextSignFormat>,
+    #[arg(
+        short,
+        long,
+        help = "Write a signature file with algorithm, key fingerprint and timestamp"
+    )]
+    pub output: Option<String>,
+    #[arg(long, help = "Trusted comment for minisign signatures")]
+    pub trusted_comment: Option<String>,
+    #[arg(long, default_value = "base64", value_parser = parse_signature_encoding, help = "Encoding of the printed signature: base64 or hex")]
+    pub encoding: SignatureEncoding,
+    #[arg(long, default_value = "raw", value_parser = parse_ecdsa_encoding, help = "ECDSA signature form: raw (r || s) or der")]
+    pub ecdsa_encoding: EcdsaEncoding,
 }
 
 impl crate::CmdEexector for TextSignOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let ret = crate::process_sign(&self.input, &self.key, self.format)?;
-        println!("{}", ret);
+        let (key, format) = resolve_signing_key(self.key, self.key_id, self.format, true)?;
+        if format == TextSignFormat::Minisign {
+            let signature =
+                crate::process_minisign_sign(&self.input, &key, self.trusted_comment.as_deref())?;
+            match &self.output {
+                Some(output) => fs::write(output, signature).await?,
+                None => print!("{}", signature),
+            }
+            return Ok(());
+        }
+        if self.trusted_comment.is_some() {
+            anyhow::bail!("--trusted-comment only applies to --format minisign");
+        }
+        match &self.output {
+            Some(_)
+                if self.encoding != SignatureEncoding::Base64
+                    || self.ecdsa_encoding != EcdsaEncoding::Raw =>
+            {
+                anyhow::bail!("--encoding and --ecdsa-encoding only apply to printed signatures, signature files use the default encodings")
+            }
+            Some(output) => {
+                let signature = crate::process_sign_detached(&self.input, &key, format)?;
+                fs::write(output, signature.to_string()).await?;
+            }
+            None => println!(
+                "{}",
+                crate::process_sign(
+                    &self.input,
+                    &key,
+                    format,
+                    self.encoding,
+                    self.ecdsa_encoding
+                )?
+            ),
+        }
         Ok(())
     }
 }
@@ -55,33 +143,228 @@ impl crate::CmdEexector for TextSignOpts {
 /// # 字段
 /// * `input` - 要验证的输入文件。它接受一个短或长参数，并使用 `file_check` 进行验证。默认值为 `-`。
 /// * `key` - 用于验证的密钥文件。它接受一个长参数，并使用 `file_check` 进行验证。
-/// * `signature` - 要验证的签名文件。它接受一个长参数。
-/// * `format` - 文本签名的格式。它接受一个长参数，并使用 `parse_format` 进行验证。默认值为 `blake3`。
+/// * `key_id` - 密钥环中的密钥名，ed25519 使用其中的公钥，不能与 `key` 同时使用。
+/// * `signature` - 要验证的 URL-safe base64 签名。它接受一个长参数。
+/// * `signature_file` - `sign --output` 写出的签名文件，不能与 `signature` 同时使用；
+///   算法取自签名文件，并检查验证密钥的指纹是否与签名密钥一致。
+///   也可以是 minisign 的 `.minisig` 文件（自动识别），此时 `key` 为 minisign 公钥，验证成功后输出可信注释。
+/// * `format` - 文本签名的格式。它接受一个长参数，并使用 `parse_format` 进行验证。使用 `key` 时默认值为 `blake3`，
+///   使用 `key_id` 时取密钥环中记录的算法，使用 `signature_file` 时取签名文件中记录的算法。
+/// * `encoding` - `signature` 的编码：`base64`（默认）或 `hex`，`hex` 允许带有 `sha256=` 这样的前缀，
+///   可以直接使用 GitHub `X-Hub-Signature-256` 请求头的值。
+/// * `quiet` - 不输出任何内容，只通过退出码反映结果。
+///
+/// HMAC 签名以常数时间比较；ECDSA 签名可以是 r || s 或 DER，自动识别。签名无效时输出 `false` 并以退出码 3 退出，与 I/O、密钥等错误（退出码 1）区分。
 
 #[derive(Debug, Parser)]
+#[command(group(ArgGroup::new("verifying_key").required(true).args(["key", "key_id"])))]
+#[command(group(ArgGroup::new("signature_source").required(true).args(["signature", "signature_file"])))]
 pub struct TextVerifyOpts {
     #[arg(short, long, value_parser = file_check,default_value = "-")]
     pub input: String,
     #[arg( long,value_parser = file_check)]
-    pub key: String,
+    pub key: Option<String>,
+    #[arg(long, help = "Name of a key in the keyring (see `rcli key`)")]
+    pub key_id: Option<String>,
     #[arg(short, long)]
-    pub signature: String,
-    #[arg(long,default_value = "blake3",value_parser =  parse_format)]
-    pub format: TextSignFormat,
+    pub signature: Option<String>,
+    #[arg(long, value_parser = file_check, help = "Signature file written by `text sign --output`")]
+    pub signature_file: Option<String>,
+    #[arg(long, value_parser = parse_format)]
+    pub format: Option<TextSignFormat>,
+    #[arg(long, default_value = "base64", value_parser = parse_signature_encoding, help = "Encoding of --signature: base64 or hex")]
+    pub encoding: SignatureEncoding,
+    #[arg(
+        short,
+        long,
+        help = "Print nothing, report the result only through the exit code"
+    )]
+    pub quiet: bool,
 }
 
 impl crate::CmdEexector for TextVerifyOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let ret = crate::process_verify(&self.input, &self.key, &self.signature, self.format)?;
-        println!("{}", ret);
+        if self.signature_file.is_some() && self.encoding != SignatureEncoding::Base64 {
+            anyhow::bail!("--encoding only applies to --signature");
+        }
+        let ret = match (&self.signature, &self.signature_file) {
+            (_, Some(path)) if crate::is_minisign(&fs::read(path).await?) => {
+                if self
+                    .format
+                    .is_some_and(|format| format != TextSignFormat::Minisign)
+                {
+                    anyhow::bail!("--format does not match the signature file (minisign)");
+                }
+                let (key, _) = resolve_signing_key(
+                    self.key,
+                    self.key_id,
+                    Some(TextSignFormat::Minisign),
+                    false,
+                )?;
+                let signature = fs::read_to_string(path).await?;
+                let trusted_comment =
+                    crate::process_minisign_verify(&self.input, &key, &signature)?;
+                if let Some(comment) = trusted_comment.as_ref().filter(|_| !self.quiet) {
+                    eprintln!("Trusted comment: {}", comment);
+                }
+                trusted_comment.is_some()
+            }
+            (_, Some(path)) => {
+                let signature = crate::SignatureFile::load(path)?;
+                let recorded = signature.format()?;
+                if self.format.is_some_and(|format| format != recorded) {
+                    anyhow::bail!("--format does not match the signature file ({})", recorded);
+                }
+                let (key, _) = resolve_signing_key(self.key, self.key_id, Some(recorded), false)?;
+                crate::process_verify_detached(&self.input, &key, &signature)?
+            }
+            (Some(signature), None) => {
+                let (key, format) = resolve_signing_key(self.key, self.key_id, self.format, false)?;
+                crate::process_verify(&self.input, &key, signature, format, self.encoding)?
+            }
+            (None, None) => anyhow::bail!("Either --signature or --signature-file is required"),
+        };
+        if !self.quiet {
+            println!("{}", ret);
+        }
+        if !ret {
+            return Err(crate::VerificationFailed::reported().into());
+        }
+        Ok(())
+    }
+}
+
+/// `TextSignDirOpts` 为发布目录生成签名清单，类似 minisign / signify 对目录树的用法。
+/// # 字段
+/// * `dir` - 要签名的目录，递归收录其中的所有普通文件，不支持符号链接。
+/// * `key` / `key_id` - Ed25519 私钥文件或密钥环中的密钥名。
+/// * `manifest` - 清单路径，默认为目录下的 `B3SUMS`，签名写入同名的 `.sig` 文件。
+/// # 示例
+/// ```shell
+/// $ rcli text sign-dir ./dist --key-id release-2026
+/// $ rcli text verify-dir ./dist --key keys/ed25519.pub
+/// ```
+/// # 注意
+/// * 清单每行为 `<blake3>  <相对路径>`，与 `b3sum` 兼容，按路径排序。
+#[derive(Debug, Parser)]
+#[command(group(ArgGroup::new("signing_key").required(true).args(["key", "key_id"])))]
+pub struct TextSignDirOpts {
+    #[arg(value_parser = path_check)]
+    pub dir: String,
+    #[arg(long, value_parser = file_check)]
+    pub key: Option<String>,
+    #[arg(long, help = "Name of a key in the keyring (see `rcli key`)")]
+    pub key_id: Option<String>,
+    #[arg(long, help = "Manifest path, defaults to <DIR>/B3SUMS")]
+    pub manifest: Option<String>,
+}
+
+impl crate::CmdEexector for TextSignDirOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let manifest = dir_manifest(&self.dir, self.manifest);
+        let (key, _) =
+            resolve_signing_key(self.key, self.key_id, Some(TextSignFormat::Ed25519), true)?;
+        let count = crate::process_sign_dir(&self.dir, &manifest, &key)?;
+        eprintln!(
+            "Signed {} file(s): {}, {}",
+            count,
+            manifest,
+            crate::manifest_signature_path(&manifest)
+        );
+        Ok(())
+    }
+}
+
+/// `TextVerifyDirOpts` 验证 `sign-dir` 生成的清单。
+/// # 字段
+/// * `dir` - 要验证的目录。
+/// * `key` / `key_id` - Ed25519 公钥文件或密钥环中的密钥名。
+/// * `manifest` - 清单路径，默认为目录下的 `B3SUMS`。
+/// * `quiet` - 不输出任何内容，只通过退出码反映结果。
+/// # 注意
+/// * 先验证清单的签名，签名无效时直接失败；之后逐个报告新增（`ADDED`）、缺失（`MISSING`）
+///   与被修改（`MODIFIED`）的文件，有任何差异时以退出码 3 退出。
+#[derive(Debug, Parser)]
+#[command(group(ArgGroup::new("verifying_key").required(true).args(["key", "key_id"])))]
+pub struct TextVerifyDirOpts {
+    #[arg(value_parser = path_check)]
+    pub dir: String,
+    #[arg(long, value_parser = file_check)]
+    pub key: Option<String>,
+    #[arg(long, help = "Name of a key in the keyring (see `rcli key`)")]
+    pub key_id: Option<String>,
+    #[arg(long, help = "Manifest path, defaults to <DIR>/B3SUMS")]
+    pub manifest: Option<String>,
+    #[arg(
+        short,
+        long,
+        help = "Print nothing, report the result only through the exit code"
+    )]
+    pub quiet: bool,
+}
+
+impl crate::CmdEexector for TextVerifyDirOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let manifest = dir_manifest(&self.dir, self.manifest);
+        let (key, _) =
+            resolve_signing_key(self.key, self.key_id, Some(TextSignFormat::Ed25519), false)?;
+        let report = crate::quiet_verification(
+            crate::process_verify_dir(&self.dir, &manifest, &key),
+            self.quiet,
+        )?;
+        if self.quiet {
+            return match report.is_ok() {
+                true => Ok(()),
+                false => Err(crate::VerificationFailed::reported().into()),
+            };
+        }
+        for file in &report.added {
+            println!("ADDED: {}", file);
+        }
+        for file in &report.missing {
+            println!("MISSING: {}", file);
+        }
+        for file in &report.modified {
+            println!("MODIFIED: {}", file);
+        }
+        if !report.is_ok() {
+            return Err(crate::VerificationFailed::new("Directory verification failed").into());
+        }
+        println!("OK: {} file(s) verified", report.checked);
         Ok(())
     }
 }
 
+fn dir_manifest(dir: &str, manifest: Option<String>) -> String {
+    manifest.unwrap_or_else(|| {
+        Path::new(dir)
+            .join(crate::DIR_MANIFEST_NAME)
+            .to_string_lossy()
+            .into_owned()
+    })
+}
+
+/// `--key` 直接使用密钥文件（算法默认为 `blake3`），`--key-id` 从密钥环取出密钥文件与算法。
+fn resolve_signing_key(
+    key: Option<String>,
+    key_id: Option<String>,
+    format: Option<TextSignFormat>,
+    signing: bool,
+) -> anyhow::Result<(String, TextSignFormat)> {
+    match (key, key_id) {
+        (Some(key), _) => Ok((key, format.unwrap_or(TextSignFormat::Blake3))),
+        (None, Some(id)) => crate::Keyring::open()?.resolve(&id, signing, format),
+        (None, None) => anyhow::bail!("Either --key or --key-id is required"),
+    }
+}
+
 /// `TextKeyGenerateOpts` 是一个用于保存生成新密钥选项的结构体。
 /// # 字段
 /// * `format` - 生成的密钥的格式。它接受一个长参数，并使用 `parse_format` 进行验证。默认值为 `ed25519`。
 /// * `path` - 生成的密钥的路径。它接受一个长参数，并使用 `path_check` 进行验证。默认值为 `keys`。
+/// * `encoding` - 密钥文件的编码：`raw`（默认）、`hex`、`base64`、`pem` 或 `openssh`（仅 ed25519），
+///   加载时自动识别。
+/// * `passphrase` - 可选，用口令（Argon2id + XChaCha20-Poly1305）加密私钥，见 `PassphraseOpts`。
 /// # 示例
 /// ```shell
 /// # 生成一个新的 ed25519 密钥
@@ -92,11 +375,29 @@ impl crate::CmdEexector for TextVerifyOpts {
 /// $ cli text generate --format base64
 /// # 生成一个新的密钥并将其保存到指定的路径
 /// $ cli text generate --path /path/to/keys
+/// # 以 PEM 格式保存，便于复制粘贴
+/// $ cli text generate --encoding pem
+/// # 生成 OpenSSH 格式的 ed25519 密钥，可与 ssh-keygen 互通
+/// $ cli text generate --encoding openssh
+/// # 用口令加密私钥
+/// $ cli text generate --passphrase
 /// ```
 /// # 注意
+/// * 密钥直接取自系统的安全随机数生成器（`OsRng`）。
+/// * 密钥文件带有类型与校验和，`sign` / `verify` / `encrypt` 用错密钥文件时会给出明确的错误。
+/// * 私钥与对称密钥文件的权限为 0600（unix），公钥使用默认权限。
+/// * 加密的私钥在 `sign` 时会提示输入口令，设置 `RCLI_KEY_PASSPHRASE` 环境变量可以跳过提示。
 /// * 如果指定的路径不存在，将会自动创建。
 /// * 生成的密钥将会保存到指定的路径下。
-/// * 生成的密钥文件名为 `blake3.key`、`ed25519.pub`、`ed25519.priv` 或 `chacha20.key`。
+/// * 生成的密钥文件名为 `blake3.key`、`ed25519.pub`、`ed25519.priv` 或 `base64.key`（ChaCha20）。
+/// * `--format ecdsa-p256`、`ecdsa-secp256k1` 与 `rsa-pss`（3072 位）生成 `<format>.priv`（PKCS#8 PEM）与
+///   `<format>.pub`（SPKI PEM），可以直接被 openssl 读取；指定口令时私钥保存为加密的 PKCS#8，
+///   只支持默认编码或 `pem`。
+/// * `--format hmac-sha256`（或 `hmac-sha384`、`hmac-sha512`）生成 32 字节的 `hmac.key`，三种哈希算法通用；
+///   签名时也可以直接用保存了 webhook 共享密钥的文本文件作为 `--key`。
+/// * `--format x25519` 生成 `x25519.priv` / `x25519.pub`，用于 `text derive` 的密钥协商，不能签名。
+/// * `--format minisign` 生成 `minisign.key` / `minisign.pub`，与 minisign 互通；指定口令时私钥使用
+///   minisign 自身的 scrypt 加密（需要约 1 GiB 内存），不支持 `--encoding`。
 
 #[derive(Debug, Parser)]
 pub struct TextKeyGenerateOpts {
@@ -104,52 +405,308 @@ pub struct TextKeyGenerateOpts {
     pub format: TextSignFormat,
     #[arg(long, default_value = "keys" , value_parser = path_check)]
     pub path: String,
+    #[arg(long, default_value = "raw", value_parser = parse_key_encoding)]
+    pub encoding: KeyEncoding,
+    #[command(flatten)]
+    pub passphrase: PassphraseOpts,
 }
 
 impl crate::CmdEexector for TextKeyGenerateOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let res = crate::create_key(self.format)?;
+        let passphrase = self.passphrase.resolve(true)?;
+        let res = Zeroizing::new(match self.format {
+            crate::cli::text::TextSignFormat::Minisign => {
+                if self.encoding != KeyEncoding::Raw {
+                    anyhow::bail!("minisign keys have their own format, --encoding does not apply");
+                }
+                // minisign 私钥使用自身的 scrypt 加密，与 `minisign -G` 生成的文件兼容
+                crate::minisign_keygen(passphrase.as_deref().map(|p| p.as_str()))?
+            }
+            format @ (crate::cli::text::TextSignFormat::EcdsaP256
+            | crate::cli::text::TextSignFormat::EcdsaSecp256k1
+            | crate::cli::text::TextSignFormat::RsaPss) => {
+                if !matches!(self.encoding, KeyEncoding::Raw | KeyEncoding::Pem) {
+                    anyhow::bail!(
+                        "{} keys are written as PKCS#8 / SPKI PEM, --encoding {} does not apply",
+                        format,
+                        self.encoding
+                    );
+                }
+                crate::create_key(format)?
+            }
+            format => crate::create_key(format)?,
+        });
+        if !Path::new(&self.path).exists() {
+            fs::create_dir(&self.path).await?;
+        }
+        // 私钥（以及对称密钥）可以用口令加密，公钥始终明文保存
+        let encode_secret = |kind, key: &[u8]| -> anyhow::Result<Vec<u8>> {
+            let key = crate::KeyFile::new(kind, key)?;
+            match &passphrase {
+                Some(passphrase) => key.encode_encrypted(self.encoding, passphrase),
+                None => key.encode(self.encoding),
+            }
+        };
         match self.format {
             crate::cli::text::TextSignFormat::Blake3 => {
-                if !Path::new(&self.path).exists() {
-                    fs::create_dir(&self.path).await?;
-                }
-                let mut file = File::create(format!("{}/blake3.key", self.path)).await?;
-                file.write_all(&res[0]).await?;
-                file.flush().await?;
+                let key = encode_secret(crate::KeyKind::Blake3, &res[0])?;
+                write_private(&format!("{}/blake3.key", self.path), &key, true)?;
             }
             crate::cli::text::TextSignFormat::Ed25519 => {
-                if !Path::new(&self.path).exists() {
-                    fs::create_dir(&self.path).await?;
-                }
-                let mut public_file = File::create(format!("{}/ed25519.pub", self.path)).await?;
-                let mut private_file = File::create(format!("{}/ed25519.priv", self.path)).await?;
-                public_file.write_all(&res[1]).await?;
-                private_file.write_all(&res[0]).await?;
-                public_file.flush().await?;
-                private_file.flush().await?;
+                let public_key = crate::KeyFile::new(crate::KeyKind::Ed25519Public, &res[1])?
+                    .encode(self.encoding)?;
+                let private_key = encode_secret(crate::KeyKind::Ed25519Private, &res[0])?;
+                write_public(&format!("{}/ed25519.pub", self.path), &public_key).await?;
+                write_private(&format!("{}/ed25519.priv", self.path), &private_key, true)?;
             }
             crate::cli::text::TextSignFormat::ChaCha20 => {
-                if !Path::new(&self.path).exists() {
-                    fs::create_dir(&self.path).await?;
-                }
-                let mut file = File::create(format!("{}/base64.key", self.path)).await?;
-                for key in res.iter() {
-                    file.write_all(key).await?;
-                }
-
-                file.flush().await?;
+                let key = encode_secret(crate::KeyKind::ChaCha20, &res[0])?;
+                write_private(&format!("{}/base64.key", self.path), &key, true)?;
+            }
+            crate::cli::text::TextSignFormat::HmacSha256
+            | crate::cli::text::TextSignFormat::HmacSha384
+            | crate::cli::text::TextSignFormat::HmacSha512 => {
+                let key = encode_secret(crate::KeyKind::Hmac, &res[0])?;
+                write_private(&format!("{}/hmac.key", self.path), &key, true)?;
+            }
+            crate::cli::text::TextSignFormat::EcdsaP256
+            | crate::cli::text::TextSignFormat::EcdsaSecp256k1
+            | crate::cli::text::TextSignFormat::RsaPss => {
+                let private_key =
+                    crate::encode_pkcs8_pem(&res[0], passphrase.as_deref().map(|p| p.as_str()))?;
+                let public_key = crate::encode_spki_pem(&res[1]);
+                write_public(
+                    &format!("{}/{}.pub", self.path, self.format),
+                    public_key.as_bytes(),
+                )
+                .await?;
+                write_private(
+                    &format!("{}/{}.priv", self.path, self.format),
+                    private_key.as_bytes(),
+                    true,
+                )?;
+            }
+            crate::cli::text::TextSignFormat::X25519 => {
+                let public_key = crate::KeyFile::new(crate::KeyKind::X25519Public, &res[1])?
+                    .encode(self.encoding)?;
+                let private_key = encode_secret(crate::KeyKind::X25519Private, &res[0])?;
+                write_public(&format!("{}/x25519.pub", self.path), &public_key).await?;
+                write_private(&format!("{}/x25519.priv", self.path), &private_key, true)?;
+            }
+            crate::cli::text::TextSignFormat::Minisign => {
+                write_public(&format!("{}/minisign.pub", self.path), &res[1]).await?;
+                write_private(&format!("{}/minisign.key", self.path), &res[0], true)?;
             }
         }
         Ok(())
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// 公钥不需要保密，以普通权限写入。
+async fn write_public(path: &str, data: &[u8]) -> anyhow::Result<()> {
+    let mut file = File::create(path).await?;
+    file.write_all(data).await?;
+    file.flush().await?;
+    Ok(())
+}
+
+/// `TextDeriveOpts` 是一个用于保存 X25519 密钥协商选项的结构体。
+/// # 字段
+/// * `key` - 我方的 X25519 私钥（`text generate --format x25519` 生成的 `x25519.priv`）。
+/// * `peer` - 对方的 X25519 公钥（对方的 `x25519.pub`）。
+/// * `output` - 派生出的 ChaCha20-Poly1305 密钥文件，可直接用于 `text encrypt --key` / `text decrypt --key`。
+/// * `context` - 可选的用途标识，参与 HKDF 派生，双方必须使用相同的值。
+/// * `encoding` - 输出密钥文件的编码。
+/// * `passphrase` - 可选，用口令加密输出的密钥文件。
+/// # 注意
+/// * 共享秘密经 HKDF-SHA256 派生，双方用各自的私钥与对方的公钥得到相同的密钥：
+/// ```shell
+/// $ rcli text derive --key alice/x25519.priv --peer bob/x25519.pub -o shared.key
+/// $ rcli text derive --key bob/x25519.priv --peer alice/x25519.pub -o shared.key
+/// ```
+/// * 同一对密钥总是派生出相同的密钥，需要多把密钥时使用不同的 `context`。
+/// * 输出文件已存在时报错，不会覆盖。
+#[derive(Debug, Parser)]
+pub struct TextDeriveOpts {
+    #[arg(short, long, value_parser = file_check)]
+    pub key: String,
+    #[arg(long, value_parser = file_check)]
+    pub peer: String,
+    #[arg(short, long)]
+    pub output: String,
+    #[arg(long, default_value = "", help = "Context bound into the derived key")]
+    pub context: String,
+    #[arg(long, default_value = "raw", value_parser = parse_key_encoding)]
+    pub encoding: KeyEncoding,
+    #[command(flatten)]
+    pub passphrase: PassphraseOpts,
+}
+
+impl crate::CmdEexector for TextDeriveOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let key = crate::process_derive(&self.key, &self.peer, self.context.as_bytes())?;
+        let data = match self.passphrase.resolve(true)? {
+            Some(passphrase) => key.encode_encrypted(self.encoding, &passphrase)?,
+            None => key.encode(self.encoding)?,
+        };
+        write_private(&self.output, &data, false)
+            .with_context(|| format!("Failed to write {}, the file must not exist", self.output))
+    }
+}
+
+/// `TextEncryptOpts` 是一个用于保存加密文本文件选项的结构体。
+/// # 字段
+/// * `input` - 要加密的输入文件。默认值为 `-`。
+/// * `secret` - 密钥文件或口令，见 `TextSecretOpts`。
+/// * `output` - 输出文件，默认写到标准输出。
+/// * `algorithm` - `chacha20poly1305` 或 `xchacha20poly1305`（默认，192 位 nonce）。
+/// * `aad` - 关联数据，不会被加密但参与认证，解密时必须提供相同的值。
+/// # 注意
+/// * 输出为二进制格式：`RCLI` 魔数、版本号、算法、nonce 前缀与若干 64 KiB 的密文段，
+///   加解密的内存占用与文件大小无关，截断或重排分段都会导致解密失败。
+/// * 每次加密都会生成新的随机 nonce，同一密钥可以安全地加密多条消息。
+/// * 口令模式会把 Argon2id 的盐和代价参数写入头部，解密时只需提供相同的口令。
+#[derive(Debug, Parser)]
+pub struct TextEncryptOpts {
+    #[arg(short, long, value_parser = file_check, default_value = "-")]
+    pub input: String,
+    #[command(flatten)]
+    pub secret: TextSecretOpts,
+    #[arg(short, long)]
+    pub output: Option<String>,
+    #[arg(long, default_value = "xchacha20poly1305", value_parser = parse_encryption_algorithm)]
+    pub algorithm: EncryptionAlgorithm,
+    #[arg(
+        long,
+        default_value = "",
+        help = "Associated data authenticated with the ciphertext"
+    )]
+    pub aad: String,
+}
+
+impl crate::CmdEexector for TextEncryptOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        crate::process_encrypt(
+            &self.input,
+            self.output.as_deref(),
+            self.secret.resolve(true)?,
+            self.algorithm,
+            self.aad.as_bytes(),
+        )
+    }
+}
+
+/// `TextDecryptOpts` 是一个用于保存解密文本文件选项的结构体。
+/// # 字段
+/// * `input` - `text encrypt` 生成的密文文件。默认值为 `-`。
+/// * `secret` - 加密时使用的密钥文件或口令。
+/// * `output` - 明文输出文件，默认写到标准输出。
+/// * `aad` - 加密时使用的关联数据。
+/// # 注意
+/// * 分段密文每段认证后立即写出；解密失败时会删除输出文件，输出到标准输出时已写出的部分需要丢弃。
+#[derive(Debug, Parser)]
+pub struct TextDecryptOpts {
+    #[arg(short, long, value_parser = file_check, default_value = "-")]
+    pub input: String,
+    #[command(flatten)]
+    pub secret: TextSecretOpts,
+    #[arg(short, long)]
+    pub output: Option<String>,
+    #[arg(
+        long,
+        default_value = "",
+        help = "Associated data used when encrypting"
+    )]
+    pub aad: String,
+}
+
+impl crate::CmdEexector for TextDecryptOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        crate::process_decrypt(
+            &self.input,
+            self.output.as_deref(),
+            self.secret.resolve(false)?,
+            self.aad.as_bytes(),
+        )
+    }
+}
+
+/// `TextSecretOpts` 是加密/解密共用的密钥来源选项，四者必须且只能指定一个。
+/// # 字段
+/// * `key` - `text generate --format base64` 生成的密钥文件。
+/// * `passphrase` - 在终端提示输入口令。
+/// * `passphrase_env` - 从指定的环境变量读取口令。
+/// * `passphrase_file` - 从文件读取口令，末尾的换行会被去掉。
+#[derive(Debug, Args)]
+#[command(group(
+    ArgGroup::new("secret")
+        .required(true)
+        .args(["key", "passphrase", "passphrase_env", "passphrase_file"])
+))]
+pub struct TextSecretOpts {
+    #[arg(long, value_parser = file_check)]
+    pub key: Option<String>,
+    #[arg(long, help = "Prompt for a passphrase on the terminal")]
+    pub passphrase: bool,
+    #[arg(
+        long,
+        value_name = "VAR",
+        help = "Read the passphrase from an environment variable"
+    )]
+    pub passphrase_env: Option<String>,
+    #[arg(long, value_name = "FILE", value_parser = file_check, help = "Read the passphrase from a file")]
+    pub passphrase_file: Option<String>,
+}
+
+impl TextSecretOpts {
+    fn resolve(&self, confirm: bool) -> anyhow::Result<crate::EncryptionKey> {
+        if let Some(key) = &self.key {
+            return Ok(crate::EncryptionKey::File(key.clone()));
+        }
+        let passphrase = crate::read_passphrase(
+            self.passphrase_env.as_deref(),
+            self.passphrase_file.as_deref(),
+            confirm,
+        )?;
+        Ok(crate::EncryptionKey::Passphrase(passphrase))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TextSignFormat {
     Blake3,
     Ed25519,
     ChaCha20,
+    Minisign,
+    HmacSha256,
+    HmacSha384,
+    HmacSha512,
+    EcdsaP256,
+    EcdsaSecp256k1,
+    RsaPss,
+    X25519,
+}
+
+impl TextSignFormat {
+    /// HMAC 的三种格式共用同一种密钥。
+    pub fn is_hmac(&self) -> bool {
+        matches!(
+            self,
+            TextSignFormat::HmacSha256 | TextSignFormat::HmacSha384 | TextSignFormat::HmacSha512
+        )
+    }
+
+    pub fn is_ecdsa(&self) -> bool {
+        matches!(
+            self,
+            TextSignFormat::EcdsaP256 | TextSignFormat::EcdsaSecp256k1
+        )
+    }
+
+    /// ECDSA 与 RSA 的密钥是 PKCS#8 / SPKI 文件，不使用 rcli 自己的密钥容器。
+    pub fn is_pkcs8(&self) -> bool {
+        self.is_ecdsa() || *self == TextSignFormat::RsaPss
+    }
 }
 
 impl FromStr for TextSignFormat {
@@ -160,6 +717,14 @@ impl FromStr for TextSignFormat {
             "blake3" => Ok(TextSignFormat::Blake3),
             "ed25519" => Ok(TextSignFormat::Ed25519),
             "base64" => Ok(TextSignFormat::ChaCha20),
+            "minisign" => Ok(TextSignFormat::Minisign),
+            "hmac-sha256" => Ok(TextSignFormat::HmacSha256),
+            "hmac-sha384" => Ok(TextSignFormat::HmacSha384),
+            "hmac-sha512" => Ok(TextSignFormat::HmacSha512),
+            "ecdsa-p256" => Ok(TextSignFormat::EcdsaP256),
+            "ecdsa-secp256k1" => Ok(TextSignFormat::EcdsaSecp256k1),
+            "rsa-pss" => Ok(TextSignFormat::RsaPss),
+            "x25519" => Ok(TextSignFormat::X25519),
             v => anyhow::bail!("Unsupported base64 format: {}", v),
         }
     }
@@ -168,13 +733,186 @@ impl FromStr for TextSignFormat {
 impl fmt::Display for TextSignFormat {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            TextSignFormat::Blake3 => write!(f, "urlsafe"),
-            TextSignFormat::Ed25519 => write!(f, "standard"),
+            TextSignFormat::Blake3 => write!(f, "blake3"),
+            TextSignFormat::Ed25519 => write!(f, "ed25519"),
             TextSignFormat::ChaCha20 => write!(f, "base64"),
+            TextSignFormat::Minisign => write!(f, "minisign"),
+            TextSignFormat::HmacSha256 => write!(f, "hmac-sha256"),
+            TextSignFormat::HmacSha384 => write!(f, "hmac-sha384"),
+            TextSignFormat::HmacSha512 => write!(f, "hmac-sha512"),
+            TextSignFormat::EcdsaP256 => write!(f, "ecdsa-p256"),
+            TextSignFormat::EcdsaSecp256k1 => write!(f, "ecdsa-secp256k1"),
+            TextSignFormat::RsaPss => write!(f, "rsa-pss"),
+            TextSignFormat::X25519 => write!(f, "x25519"),
+        }
+    }
+}
+
+pub(crate) fn parse_format(s: &str) -> Result<TextSignFormat, anyhow::Error> {
+    s.parse()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptionAlgorithm {
+    ChaCha20Poly1305,
+    XChaCha20Poly1305,
+}
+
+impl FromStr for EncryptionAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "chacha20poly1305" => Ok(EncryptionAlgorithm::ChaCha20Poly1305),
+            "xchacha20poly1305" => Ok(EncryptionAlgorithm::XChaCha20Poly1305),
+            v => anyhow::bail!("Unsupported encryption algorithm: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for EncryptionAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EncryptionAlgorithm::ChaCha20Poly1305 => write!(f, "chacha20poly1305"),
+            EncryptionAlgorithm::XChaCha20Poly1305 => write!(f, "xchacha20poly1305"),
+        }
+    }
+}
+
+fn parse_encryption_algorithm(s: &str) -> Result<EncryptionAlgorithm, anyhow::Error> {
+    s.parse()
+}
+
+/// 密钥文件的存储编码，加载时会自动识别。
+/// * `Raw` / `Hex` / `Base64` - 带类型与校验和的二进制容器及其文本编码。
+/// * `Pem` - ed25519 使用标准的 PKCS#8（私钥）/ SPKI（公钥），其他算法使用 `rcli` 自定义标签。
+/// * `Openssh` - 仅适用于 ed25519：OpenSSH 私钥格式与 `ssh-ed25519` 公钥行。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEncoding {
+    Raw,
+    Hex,
+    Base64,
+    Pem,
+    Openssh,
+}
+
+impl FromStr for KeyEncoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "raw" => Ok(KeyEncoding::Raw),
+            "hex" => Ok(KeyEncoding::Hex),
+            "base64" => Ok(KeyEncoding::Base64),
+            "pem" => Ok(KeyEncoding::Pem),
+            "openssh" => Ok(KeyEncoding::Openssh),
+            v => anyhow::bail!("Unsupported key encoding: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for KeyEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyEncoding::Raw => write!(f, "raw"),
+            KeyEncoding::Hex => write!(f, "hex"),
+            KeyEncoding::Base64 => write!(f, "base64"),
+            KeyEncoding::Pem => write!(f, "pem"),
+            KeyEncoding::Openssh => write!(f, "openssh"),
+        }
+    }
+}
+
+pub(crate) fn parse_key_encoding(s: &str) -> Result<KeyEncoding, anyhow::Error> {
+    s.parse()
+}
+
+/// 签名的文本编码：默认为 URL-safe base64（无填充）；`hex` 为小写十六进制，
+/// 与 GitHub、Stripe 等 webhook 在请求头中发送的 HMAC 签名格式一致。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureEncoding {
+    Base64,
+    Hex,
+}
+
+impl SignatureEncoding {
+    pub fn encode(&self, signature: &[u8]) -> String {
+        match self {
+            SignatureEncoding::Base64 => URL_SAFE_NO_PAD.encode(signature),
+            SignatureEncoding::Hex => hex::encode(signature),
+        }
+    }
+
+    /// `hex` 允许带有 `sha256=` 这样的算法前缀（GitHub 的 `X-Hub-Signature-256`），大小写不敏感。
+    pub fn decode(&self, signature: &str) -> anyhow::Result<Vec<u8>> {
+        let signature = signature.trim();
+        match self {
+            SignatureEncoding::Base64 => Ok(URL_SAFE_NO_PAD.decode(signature)?),
+            SignatureEncoding::Hex => {
+                let signature = match signature.split_once('=') {
+                    Some((prefix, hex)) if prefix.chars().all(|c| c.is_ascii_alphanumeric()) => hex,
+                    _ => signature,
+                };
+                Ok(hex::decode(signature)?)
+            }
+        }
+    }
+}
+
+impl FromStr for SignatureEncoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "base64" => Ok(SignatureEncoding::Base64),
+            "hex" => Ok(SignatureEncoding::Hex),
+            v => anyhow::bail!("Unsupported signature encoding: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for SignatureEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SignatureEncoding::Base64 => write!(f, "base64"),
+            SignatureEncoding::Hex => write!(f, "hex"),
+        }
+    }
+}
+
+pub(crate) fn parse_signature_encoding(s: &str) -> Result<SignatureEncoding, anyhow::Error> {
+    s.parse()
+}
+
+/// ECDSA 签名的形式：`raw` 为定长的 r || s（JWS、WebCrypto 使用），
+/// `der` 为 ASN.1 DER（openssl、Java 使用）。验证时两种形式都接受。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EcdsaEncoding {
+    Raw,
+    Der,
+}
+
+impl FromStr for EcdsaEncoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "raw" => Ok(EcdsaEncoding::Raw),
+            "der" => Ok(EcdsaEncoding::Der),
+            v => anyhow::bail!("Unsupported ECDSA signature encoding: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for EcdsaEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EcdsaEncoding::Raw => write!(f, "raw"),
+            EcdsaEncoding::Der => write!(f, "der"),
         }
     }
 }
 
-fn parse_format(s: &str) -> Result<TextSignFormat, anyhow::Error> {
+pub(crate) fn parse_ecdsa_encoding(s: &str) -> Result<EcdsaEncoding, anyhow::Error> {
     s.parse()
 }
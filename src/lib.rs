@@ -7,6 +7,11 @@ use enum_dispatch::enum_dispatch;
 pub use process::*;
 pub use utils::*;
 
+/// 所有子命令的执行入口。返回的错误决定进程的退出码：
+/// * 0 - 成功；
+/// * 1 - I/O、密钥、格式等错误（`EXIT_ERROR`）；
+/// * 2 - 参数错误（`EXIT_USAGE`，由 clap 处理）；
+/// * 3 - 验证未通过（`EXIT_VERIFY_FAILED`），返回 `VerificationFailed`。
 #[allow(async_fn_in_trait)]
 #[enum_dispatch]
 pub trait CmdEexector {
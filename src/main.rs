@@ -1,11 +1,20 @@
-use anyhow::Result;
+use std::process::ExitCode;
+
 use clap::Parser;
-use rcli::{cli::Opts, CmdEexector};
+use rcli::{allow_legacy_keys, cli::Opts, exit_code, should_report, CmdEexector};
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     tracing_subscriber::fmt::init();
     let opts = Opts::parse();
-    opts.cmd.execute().await?;
-    Ok(())
+    allow_legacy_keys(opts.legacy_key);
+    match opts.cmd.execute().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            if should_report(&e) {
+                eprintln!("Error: {:?}", e);
+            }
+            ExitCode::from(exit_code(&e))
+        }
+    }
 }
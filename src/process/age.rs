@@ -0,0 +1,885 @@
+use std::{
+    fmt, fs,
+    io::{BufRead, BufReader, Cursor, Read, Write},
+    str::FromStr,
+};
+
+use anyhow::{Context, Result};
+use base64::{
+    engine::general_purpose::{STANDARD, STANDARD_NO_PAD},
+    write::EncoderWriter,
+    Engine as _,
+};
+use bech32::{FromBase32, ToBase32, Variant};
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, KeyInit, OsRng},
+    ChaCha20Poly1305, Nonce,
+};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use x25519_dalek::{PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+use super::{
+    b64::LineWrapWriter,
+    encrypt::{open_stream, seal_stream},
+};
+use crate::{get_reader, get_writer};
+
+const INTRO: &[u8] = b"age-encryption.org/v1";
+const VERSION_PREFIX: &[u8] = b"age-encryption.org/";
+const STANZA_PREFIX: &[u8] = b"-> ";
+const MAC_PREFIX: &[u8] = b"---";
+const ARMOR_BEGIN: &str = "-----BEGIN AGE ENCRYPTED FILE-----";
+const ARMOR_END: &str = "-----END AGE ENCRYPTED FILE-----";
+const LINE_LEN: usize = 64;
+/// 头部单行长度上限，防止恶意输入让解析占用大量内存。
+const MAX_HEADER_LINE: usize = 4096;
+
+const X25519_TAG: &str = "X25519";
+const X25519_INFO: &[u8] = b"age-encryption.org/v1/X25519";
+const SCRYPT_TAG: &str = "scrypt";
+const SCRYPT_SALT_LABEL: &[u8] = b"age-encryption.org/v1/scrypt";
+const SCRYPT_SALT_LEN: usize = 16;
+
+const FILE_KEY_LEN: usize = 16;
+const WRAPPED_KEY_LEN: usize = FILE_KEY_LEN + 16;
+const PAYLOAD_NONCE_LEN: usize = 16;
+/// age 的 STREAM nonce 为 11 字节大端计数器加 1 字节最后一段标志，
+/// 与前缀全 0 的 32 位计数器 STREAM 完全一致。
+const STREAM_NONCE_PREFIX: [u8; 7] = [0; 7];
+
+const PUBLIC_KEY_HRP: &str = "age";
+const SECRET_KEY_HRP: &str = "age-secret-key-";
+
+/// 口令加密默认的 scrypt 代价（log2 N），与 age 一致。
+pub const AGE_DEFAULT_WORK_FACTOR: u8 = 18;
+/// 解密时允许的最大 scrypt 代价。
+pub const AGE_MAX_WORK_FACTOR: u8 = 22;
+
+/// 解密失败的类别，与 age 规范测试向量的分类一致。
+#[derive(Debug)]
+pub enum AgeError {
+    Armor(String),
+    Header(String),
+    NoMatch,
+    Hmac,
+    Payload(String),
+}
+
+impl fmt::Display for AgeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AgeError::Armor(e) => write!(f, "Invalid armor: {}", e),
+            AgeError::Header(e) => write!(f, "Invalid age header: {}", e),
+            AgeError::NoMatch => write!(f, "No identity matched any of the recipients"),
+            AgeError::Hmac => write!(f, "Header MAC verification failed"),
+            AgeError::Payload(e) => write!(f, "Payload decryption failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AgeError {}
+
+fn armor_err(msg: &str) -> anyhow::Error {
+    AgeError::Armor(msg.to_string()).into()
+}
+
+fn header_err(msg: &str) -> anyhow::Error {
+    AgeError::Header(msg.to_string()).into()
+}
+
+/// 加密的接收方：X25519 公钥（`age1...`）或口令（scrypt）。
+pub enum AgeRecipient {
+    X25519(PublicKey),
+    Scrypt {
+        passphrase: Zeroizing<String>,
+        work_factor: u8,
+    },
+}
+
+/// 解密的身份：X25519 私钥（`AGE-SECRET-KEY-1...`）或口令（scrypt）。
+pub enum AgeIdentity {
+    X25519(StaticSecret),
+    Scrypt {
+        passphrase: Zeroizing<String>,
+        max_work_factor: u8,
+    },
+}
+
+impl FromStr for AgeRecipient {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let key = decode_bech32(s, PUBLIC_KEY_HRP)
+            .with_context(|| format!("Invalid age recipient: {}", s))?;
+        Ok(AgeRecipient::X25519(PublicKey::from(key)))
+    }
+}
+
+impl fmt::Display for AgeRecipient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AgeRecipient::X25519(key) => {
+                let encoded =
+                    bech32::encode(PUBLIC_KEY_HRP, key.as_bytes().to_base32(), Variant::Bech32)
+                        .map_err(|_| fmt::Error)?;
+                write!(f, "{}", encoded)
+            }
+            AgeRecipient::Scrypt { .. } => write!(f, "scrypt"),
+        }
+    }
+}
+
+impl FromStr for AgeIdentity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s != s.to_uppercase() {
+            anyhow::bail!("Invalid age identity: secret keys must be upper case");
+        }
+        let key = decode_bech32(s, SECRET_KEY_HRP).context("Invalid age identity")?;
+        Ok(AgeIdentity::X25519(StaticSecret::from(key)))
+    }
+}
+
+impl AgeIdentity {
+    pub fn generate() -> Self {
+        AgeIdentity::X25519(StaticSecret::random_from_rng(OsRng))
+    }
+
+    /// 对应的接收方，口令身份没有公钥。
+    pub fn to_recipient(&self) -> Option<AgeRecipient> {
+        match self {
+            AgeIdentity::X25519(secret) => Some(AgeRecipient::X25519(PublicKey::from(secret))),
+            AgeIdentity::Scrypt { .. } => None,
+        }
+    }
+
+    pub fn to_secret_string(&self) -> Option<Zeroizing<String>> {
+        match self {
+            AgeIdentity::X25519(secret) => {
+                let encoded = bech32::encode(
+                    SECRET_KEY_HRP,
+                    secret.as_bytes().to_base32(),
+                    Variant::Bech32,
+                )
+                .ok()?;
+                Some(Zeroizing::new(encoded.to_uppercase()))
+            }
+            AgeIdentity::Scrypt { .. } => None,
+        }
+    }
+
+    /// 尝试用该身份解开一个 stanza 中的文件密钥，不匹配时返回 `None`。
+    fn unwrap_file_key(&self, stanza: &Stanza) -> Result<Option<Zeroizing<[u8; FILE_KEY_LEN]>>> {
+        match (self, stanza.tag.as_str()) {
+            (AgeIdentity::X25519(secret), X25519_TAG) => {
+                let share = parse_x25519_stanza(stanza)?;
+                let shared = secret.diffie_hellman(&PublicKey::from(share));
+                if !shared.was_contributory() {
+                    return Err(header_err("X25519 share is a low-order point"));
+                }
+                let mut salt = share.to_vec();
+                salt.extend_from_slice(PublicKey::from(secret).as_bytes());
+                let key = hkdf(shared.as_bytes(), &salt, X25519_INFO);
+                Ok(aead_unwrap(&key, &stanza.body))
+            }
+            (
+                AgeIdentity::Scrypt {
+                    passphrase,
+                    max_work_factor,
+                },
+                SCRYPT_TAG,
+            ) => {
+                let (salt, work_factor) = parse_scrypt_stanza(stanza)?;
+                if work_factor > *max_work_factor {
+                    return Err(header_err(&format!(
+                        "scrypt work factor {} exceeds the maximum of {}",
+                        work_factor, max_work_factor
+                    )));
+                }
+                let key = scrypt_key(passphrase, &salt, work_factor)?;
+                Ok(aead_unwrap(&key, &stanza.body))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+impl AgeRecipient {
+    fn wrap_file_key(&self, file_key: &[u8; FILE_KEY_LEN]) -> Result<Stanza> {
+        match self {
+            AgeRecipient::X25519(recipient) => {
+                let ephemeral = StaticSecret::random_from_rng(OsRng);
+                let share = PublicKey::from(&ephemeral);
+                let shared = ephemeral.diffie_hellman(recipient);
+                if !shared.was_contributory() {
+                    anyhow::bail!("Invalid age recipient: low-order public key");
+                }
+                let mut salt = share.as_bytes().to_vec();
+                salt.extend_from_slice(recipient.as_bytes());
+                let key = hkdf(shared.as_bytes(), &salt, X25519_INFO);
+                Ok(Stanza {
+                    tag: X25519_TAG.to_string(),
+                    args: vec![STANDARD_NO_PAD.encode(share.as_bytes())],
+                    body: aead_wrap(&key, file_key)?,
+                })
+            }
+            AgeRecipient::Scrypt {
+                passphrase,
+                work_factor,
+            } => {
+                let mut salt = [0u8; SCRYPT_SALT_LEN];
+                OsRng.fill_bytes(&mut salt);
+                let key = scrypt_key(passphrase, &salt, *work_factor)?;
+                Ok(Stanza {
+                    tag: SCRYPT_TAG.to_string(),
+                    args: vec![STANDARD_NO_PAD.encode(salt), work_factor.to_string()],
+                    body: aead_wrap(&key, file_key)?,
+                })
+            }
+        }
+    }
+}
+
+fn decode_bech32(s: &str, hrp: &str) -> Result<[u8; 32]> {
+    let (decoded_hrp, data, variant) = bech32::decode(s)?;
+    if decoded_hrp != hrp || variant != Variant::Bech32 {
+        anyhow::bail!("unexpected key type");
+    }
+    let key = Vec::<u8>::from_base32(&data)?;
+    key.try_into()
+        .map_err(|_| anyhow::anyhow!("key must be 32 bytes"))
+}
+
+fn hkdf(ikm: &[u8], salt: &[u8], info: &[u8]) -> Zeroizing<[u8; 32]> {
+    let mut okm = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(Some(salt), ikm)
+        .expand(info, okm.as_mut())
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    okm
+}
+
+fn scrypt_key(passphrase: &str, salt: &[u8], work_factor: u8) -> Result<Zeroizing<[u8; 32]>> {
+    let mut full_salt = SCRYPT_SALT_LABEL.to_vec();
+    full_salt.extend_from_slice(salt);
+    let params = scrypt::Params::new(work_factor, 8, 1, 32)
+        .map_err(|e| anyhow::anyhow!("Invalid scrypt parameters: {}", e))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    scrypt::scrypt(passphrase.as_bytes(), &full_salt, &params, key.as_mut())
+        .map_err(|e| anyhow::anyhow!("scrypt failed: {}", e))?;
+    Ok(key)
+}
+
+fn aead_wrap(key: &[u8; 32], file_key: &[u8; FILE_KEY_LEN]) -> Result<Vec<u8>> {
+    ChaCha20Poly1305::new(key.into())
+        .encrypt(&Nonce::default(), &file_key[..])
+        .map_err(|e| anyhow::anyhow!(e))
+}
+
+fn aead_unwrap(key: &[u8; 32], body: &[u8]) -> Option<Zeroizing<[u8; FILE_KEY_LEN]>> {
+    let file_key = ChaCha20Poly1305::new(key.into())
+        .decrypt(&Nonce::default(), body)
+        .ok()?;
+    let mut key = Zeroizing::new([0u8; FILE_KEY_LEN]);
+    key.copy_from_slice(&file_key);
+    Some(key)
+}
+
+fn header_mac(file_key: &[u8], header: &[u8]) -> Hmac<Sha256> {
+    let key = hkdf(file_key, &[], b"header");
+    let mut mac =
+        <Hmac<Sha256> as Mac>::new_from_slice(key.as_ref()).expect("HMAC accepts any key length");
+    mac.update(header);
+    mac
+}
+
+/// 头部中的一个接收方记录：`-> tag args...` 加上 base64 正文。
+struct Stanza {
+    tag: String,
+    args: Vec<String>,
+    body: Vec<u8>,
+}
+
+impl Stanza {
+    /// 正文每 64 个字符换行，并总是以一行不足 64 个字符（可以为空）的行结束。
+    fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(STANZA_PREFIX);
+        buf.extend_from_slice(self.tag.as_bytes());
+        for arg in &self.args {
+            buf.push(b' ');
+            buf.extend_from_slice(arg.as_bytes());
+        }
+        buf.push(b'\n');
+        let body = STANDARD_NO_PAD.encode(&self.body);
+        for line in body.as_bytes().chunks(LINE_LEN) {
+            buf.extend_from_slice(line);
+            buf.push(b'\n');
+        }
+        if body.len().is_multiple_of(LINE_LEN) {
+            buf.push(b'\n');
+        }
+    }
+}
+
+struct Header {
+    stanzas: Vec<Stanza>,
+    /// 从文件开头到 `---`（含）的原始字节，即 MAC 覆盖的范围。
+    mac_input: Vec<u8>,
+    mac: Vec<u8>,
+}
+
+/// 读取一行（必须以 `\n` 结尾），同时记录原始字节。
+fn read_line<R: BufRead>(reader: &mut R, raw: &mut Vec<u8>) -> Result<Vec<u8>> {
+    let mut line = Vec::new();
+    reader
+        .by_ref()
+        .take(MAX_HEADER_LINE as u64)
+        .read_until(b'\n', &mut line)?;
+    if line.pop() != Some(b'\n') {
+        return Err(header_err("unexpected end of header"));
+    }
+    raw.extend_from_slice(&line);
+    raw.push(b'\n');
+    Ok(line)
+}
+
+/// 严格解码：必须是规范的无填充 base64。
+fn decode_b64(data: &[u8]) -> Result<Vec<u8>> {
+    STANDARD_NO_PAD
+        .decode(data)
+        .map_err(|_| header_err("invalid base64"))
+}
+
+fn read_header<R: BufRead>(reader: &mut R) -> Result<Header> {
+    let mut raw = Vec::new();
+    let intro = read_line(reader, &mut raw)?;
+    if intro != INTRO {
+        return Err(if intro.starts_with(VERSION_PREFIX) {
+            header_err("unsupported age version")
+        } else {
+            header_err("not an age file")
+        });
+    }
+
+    let mut stanzas = Vec::new();
+    loop {
+        let line = read_line(reader, &mut raw)?;
+        if let Some(args) = line.strip_prefix(STANZA_PREFIX) {
+            stanzas.push(read_stanza(args, reader, &mut raw)?);
+        } else if let Some(mac) = line.strip_prefix(b"--- ".as_slice()) {
+            let start = raw.len() - line.len() - 1;
+            let mac_input = raw[..start + MAC_PREFIX.len()].to_vec();
+            let mac = decode_b64(mac)?;
+            if mac.len() != 32 {
+                return Err(header_err("header MAC must be 32 bytes"));
+            }
+            if stanzas.is_empty() {
+                return Err(header_err("no recipient stanzas"));
+            }
+            return Ok(Header {
+                stanzas,
+                mac_input,
+                mac,
+            });
+        } else {
+            return Err(header_err("unexpected header line"));
+        }
+    }
+}
+
+fn read_stanza<R: BufRead>(args: &[u8], reader: &mut R, raw: &mut Vec<u8>) -> Result<Stanza> {
+    let mut args = args
+        .split(|b| *b == b' ')
+        .map(|arg| {
+            if arg.is_empty() || !arg.iter().all(|b| (0x21..=0x7e).contains(b)) {
+                return Err(header_err("invalid stanza argument"));
+            }
+            Ok(String::from_utf8(arg.to_vec())?)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let tag = args.remove(0);
+
+    let mut body = Vec::new();
+    loop {
+        let line = read_line(reader, raw)?;
+        if line.len() > LINE_LEN {
+            return Err(header_err("stanza body line is too long"));
+        }
+        body.extend_from_slice(&line);
+        if line.len() < LINE_LEN {
+            break;
+        }
+    }
+    Ok(Stanza {
+        tag,
+        args,
+        body: decode_b64(&body)?,
+    })
+}
+
+fn parse_x25519_stanza(stanza: &Stanza) -> Result<[u8; 32]> {
+    let [share] = stanza.args.as_slice() else {
+        return Err(header_err("X25519 stanza must have exactly one argument"));
+    };
+    let share: [u8; 32] = decode_b64(share.as_bytes())?
+        .try_into()
+        .map_err(|_| header_err("X25519 share must be 32 bytes"))?;
+    if stanza.body.len() != WRAPPED_KEY_LEN {
+        return Err(header_err("X25519 stanza body must be 32 bytes"));
+    }
+    Ok(share)
+}
+
+fn parse_scrypt_stanza(stanza: &Stanza) -> Result<([u8; SCRYPT_SALT_LEN], u8)> {
+    let [salt, work_factor] = stanza.args.as_slice() else {
+        return Err(header_err("scrypt stanza must have exactly two arguments"));
+    };
+    let salt: [u8; SCRYPT_SALT_LEN] = decode_b64(salt.as_bytes())?
+        .try_into()
+        .map_err(|_| header_err("scrypt salt must be 16 bytes"))?;
+    // 只接受不带前导 0 的十进制数
+    if work_factor.starts_with('0') || !work_factor.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(header_err("invalid scrypt work factor"));
+    }
+    let work_factor: u8 = work_factor
+        .parse()
+        .ok()
+        .filter(|w| (1..64).contains(w))
+        .ok_or_else(|| header_err("invalid scrypt work factor"))?;
+    if stanza.body.len() != WRAPPED_KEY_LEN {
+        return Err(header_err("scrypt stanza body must be 32 bytes"));
+    }
+    Ok((salt, work_factor))
+}
+
+/// 在尝试解密之前检查所有已知类型 stanza 的格式。
+fn validate_stanzas(stanzas: &[Stanza]) -> Result<()> {
+    let scrypt = stanzas.iter().filter(|s| s.tag == SCRYPT_TAG).count();
+    if scrypt > 0 && stanzas.len() > 1 {
+        return Err(header_err("scrypt stanzas must be alone in the header"));
+    }
+    for stanza in stanzas {
+        match stanza.tag.as_str() {
+            X25519_TAG => {
+                parse_x25519_stanza(stanza)?;
+            }
+            SCRYPT_TAG => {
+                parse_scrypt_stanza(stanza)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// 严格解析 ASCII armor：首尾允许空白，正文每行 64 个字符，最后一行 1 到 64 个字符，
+/// 必须是带填充的规范 base64，允许 CRLF 换行。
+fn dearmor(text: &[u8]) -> Result<Vec<u8>> {
+    let text = std::str::from_utf8(text).map_err(|_| armor_err("armored data is not UTF-8"))?;
+    let lines: Vec<&str> = text
+        .trim_matches(|c: char| c.is_ascii_whitespace())
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect();
+    if lines.len() < 2 || lines[0] != ARMOR_BEGIN || lines[lines.len() - 1] != ARMOR_END {
+        return Err(armor_err("missing armor begin or end line"));
+    }
+    let body = &lines[1..lines.len() - 1];
+    for (i, line) in body.iter().enumerate() {
+        let last = i + 1 == body.len();
+        if line.is_empty() || line.len() > LINE_LEN || (!last && line.len() != LINE_LEN) {
+            return Err(armor_err("invalid armor line length"));
+        }
+    }
+    STANDARD
+        .decode(body.concat())
+        .map_err(|_| armor_err("invalid base64 in armored data"))
+}
+
+/// 加密为 age v1 格式，`armor` 为 true 时输出 PEM 风格的 ASCII armor。
+pub fn encrypt_stream<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    recipients: &[AgeRecipient],
+    armor: bool,
+) -> Result<u64> {
+    if recipients.is_empty() {
+        anyhow::bail!("At least one recipient is required");
+    }
+    if recipients.len() > 1
+        && recipients
+            .iter()
+            .any(|r| matches!(r, AgeRecipient::Scrypt { .. }))
+    {
+        anyhow::bail!("A passphrase cannot be combined with other recipients");
+    }
+
+    let mut file_key = Zeroizing::new([0u8; FILE_KEY_LEN]);
+    OsRng.fill_bytes(file_key.as_mut());
+    let mut header = INTRO.to_vec();
+    header.push(b'\n');
+    for recipient in recipients {
+        recipient.wrap_file_key(&file_key)?.write_to(&mut header);
+    }
+    header.extend_from_slice(MAC_PREFIX);
+    let mac = header_mac(file_key.as_ref(), &header)
+        .finalize()
+        .into_bytes();
+    header.push(b' ');
+    header.extend_from_slice(STANDARD_NO_PAD.encode(mac).as_bytes());
+    header.push(b'\n');
+
+    let mut nonce = [0u8; PAYLOAD_NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let payload_key = hkdf(file_key.as_ref(), &nonce, b"payload");
+
+    if !armor {
+        writer.write_all(&header)?;
+        writer.write_all(&nonce)?;
+        return seal_stream::<ChaCha20Poly1305, _, _>(
+            &payload_key,
+            &STREAM_NONCE_PREFIX,
+            b"",
+            reader,
+            writer,
+        );
+    }
+
+    writeln!(writer, "{}", ARMOR_BEGIN)?;
+    let n = {
+        let mut wrapper = LineWrapWriter::new(&mut *writer, LINE_LEN, b"\n");
+        let mut encoder = EncoderWriter::new(&mut wrapper, &STANDARD);
+        encoder.write_all(&header)?;
+        encoder.write_all(&nonce)?;
+        let n = seal_stream::<ChaCha20Poly1305, _, _>(
+            &payload_key,
+            &STREAM_NONCE_PREFIX,
+            b"",
+            reader,
+            &mut encoder,
+        )?;
+        encoder.finish()?.finish()?;
+        n
+    };
+    writeln!(writer, "{}", ARMOR_END)?;
+    Ok(n)
+}
+
+/// 解密 age v1 文件，自动识别 ASCII armor（armor 输入会先整体读入内存）。
+/// 每个 STREAM 分段认证通过后立即写出，出错时 `writer` 中可能已经有部分明文。
+pub fn decrypt_stream<R: Read, W: Write>(
+    reader: R,
+    writer: &mut W,
+    identities: &[AgeIdentity],
+) -> Result<u64> {
+    let mut reader = BufReader::new(reader);
+    let armored = matches!(reader.fill_buf()?.first(), Some(b) if *b != b'a');
+    if armored {
+        let mut text = Vec::new();
+        reader.read_to_end(&mut text)?;
+        let data = dearmor(&text)?;
+        return decrypt_binary(&mut Cursor::new(data), writer, identities);
+    }
+    decrypt_binary(&mut reader, writer, identities)
+}
+
+fn decrypt_binary<R: BufRead, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    identities: &[AgeIdentity],
+) -> Result<u64> {
+    let header = read_header(reader)?;
+    validate_stanzas(&header.stanzas)?;
+
+    let mut file_key = None;
+    'outer: for identity in identities {
+        for stanza in &header.stanzas {
+            if let Some(key) = identity.unwrap_file_key(stanza)? {
+                file_key = Some(key);
+                break 'outer;
+            }
+        }
+    }
+    let file_key = file_key.ok_or(AgeError::NoMatch)?;
+    header_mac(file_key.as_ref(), &header.mac_input)
+        .verify_slice(&header.mac)
+        .map_err(|_| AgeError::Hmac)?;
+
+    let mut nonce = [0u8; PAYLOAD_NONCE_LEN];
+    reader
+        .read_exact(&mut nonce)
+        .map_err(|_| header_err("missing payload nonce"))?;
+    let payload_key = hkdf(file_key.as_ref(), &nonce, b"payload");
+    open_stream::<ChaCha20Poly1305, _, _>(&payload_key, &STREAM_NONCE_PREFIX, b"", reader, writer)
+        .map_err(|e| AgeError::Payload(e.to_string()).into())
+}
+
+/// 解析命令行中的接收方与接收方文件（每行一个 `age1...`，`#` 开头为注释）。
+pub fn read_age_recipients(recipients: &[String], files: &[String]) -> Result<Vec<AgeRecipient>> {
+    let mut ret = recipients
+        .iter()
+        .map(|r| r.parse())
+        .collect::<Result<Vec<_>>>()?;
+    for file in files {
+        let content = fs::read_to_string(file)?;
+        for line in key_lines(&content) {
+            ret.push(line.parse().with_context(|| file.clone())?);
+        }
+    }
+    Ok(ret)
+}
+
+/// 解析身份文件（`age-keygen` / `rcli age keygen` 的输出）。
+pub fn read_age_identities(files: &[String]) -> Result<Vec<AgeIdentity>> {
+    let mut ret = Vec::new();
+    for file in files {
+        let content = Zeroizing::new(fs::read_to_string(file)?);
+        for line in key_lines(&content) {
+            ret.push(line.parse().with_context(|| file.clone())?);
+        }
+    }
+    Ok(ret)
+}
+
+fn key_lines(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+pub fn process_age_encrypt(
+    input: &str,
+    output: Option<&str>,
+    recipients: &[AgeRecipient],
+    armor: bool,
+) -> Result<()> {
+    let mut reader = get_reader(input)?;
+    let mut writer = get_writer(output)?;
+    encrypt_stream(&mut reader, &mut writer, recipients, armor)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// 解密失败时会删除已写出的输出文件。
+pub fn process_age_decrypt(
+    input: &str,
+    output: Option<&str>,
+    identities: &[AgeIdentity],
+) -> Result<()> {
+    let reader = get_reader(input)?;
+    let mut writer = get_writer(output)?;
+    let ret = decrypt_stream(reader, &mut writer, identities).and_then(|_| Ok(writer.flush()?));
+    if ret.is_err() {
+        drop(writer);
+        if let Some(path) = output.filter(|o| *o != "-") {
+            let _ = fs::remove_file(path);
+        }
+    }
+    ret
+}
+
+/// 生成新的 X25519 身份，返回 `(身份文件内容, 公钥)`。
+pub fn process_age_keygen() -> Result<(Zeroizing<String>, String)> {
+    let identity = AgeIdentity::generate();
+    let recipient = identity
+        .to_recipient()
+        .context("X25519 identity always has a recipient")?
+        .to_string();
+    let secret = identity
+        .to_secret_string()
+        .context("Failed to encode the identity")?;
+    let content = Zeroizing::new(format!(
+        "# public key: {}\n{}\n",
+        recipient,
+        secret.as_str()
+    ));
+    Ok((content, recipient))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::Digest;
+    use std::path::Path;
+
+    const TESTKIT: &str = "fixtures/age/testkit";
+
+    /// 运行 age 规范（C2SP CCTV）的测试向量：检查失败类别与输出明文的哈希。
+    fn run_vector(path: &Path) -> Result<(), String> {
+        let data = fs::read(path).unwrap();
+        let split = data
+            .windows(2)
+            .position(|w| w == b"\n\n")
+            .expect("metadata header");
+        let meta = std::str::from_utf8(&data[..split]).unwrap();
+        let mut body = data[split + 2..].to_vec();
+
+        let mut expect = "";
+        let mut payload = None;
+        let mut identities = Vec::new();
+        for line in meta.lines() {
+            let (key, value) = line.split_once(": ").unwrap();
+            match key {
+                "expect" => expect = value,
+                "payload" => payload = Some(value),
+                "identity" => identities.push(value.parse::<AgeIdentity>().unwrap()),
+                "passphrase" => identities.push(AgeIdentity::Scrypt {
+                    passphrase: Zeroizing::new(value.to_string()),
+                    max_work_factor: 16,
+                }),
+                "compressed" => {
+                    let mut out = Vec::new();
+                    flate2::read::ZlibDecoder::new(&body[..])
+                        .read_to_end(&mut out)
+                        .unwrap();
+                    body = out;
+                }
+                _ => {}
+            }
+        }
+
+        let mut out = Vec::new();
+        let ret = decrypt_stream(&body[..], &mut out, &identities);
+        let got = match &ret {
+            Ok(_) => "success",
+            Err(e) => match e.downcast_ref::<AgeError>() {
+                Some(AgeError::Armor(_)) => "armor failure",
+                Some(AgeError::Header(_)) => "header failure",
+                Some(AgeError::NoMatch) => "no match",
+                Some(AgeError::Hmac) => "HMAC failure",
+                Some(AgeError::Payload(_)) => "payload failure",
+                None => "other",
+            },
+        };
+        if got != expect {
+            return Err(format!(
+                "expected {}, got {} ({:?})",
+                expect,
+                got,
+                ret.err()
+            ));
+        }
+        if let Some(payload) = payload {
+            let hash = hex::encode(Sha256::digest(&out));
+            if hash != payload {
+                return Err("payload hash mismatch".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_age_testkit() {
+        let mut entries: Vec<_> = fs::read_dir(TESTKIT)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .collect();
+        entries.sort();
+        assert!(entries.len() > 100);
+        let failures: Vec<_> = entries
+            .iter()
+            .filter_map(|path| {
+                run_vector(path)
+                    .err()
+                    .map(|e| format!("{}: {}", path.display(), e))
+            })
+            .collect();
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    #[test]
+    fn test_age_x25519_roundtrip() -> Result<()> {
+        let alice = AgeIdentity::generate();
+        let bob = AgeIdentity::generate();
+        let recipients = vec![alice.to_recipient().unwrap(), bob.to_recipient().unwrap()];
+        for size in [0, 1, 64 * 1024, 64 * 1024 + 1, 200_000] {
+            let data: Vec<u8> = (0..size).map(|i| (i % 251) as u8).collect();
+            for armor in [false, true] {
+                let mut sealed = Vec::new();
+                encrypt_stream(&mut &data[..], &mut sealed, &recipients, armor)?;
+                assert_eq!(sealed.starts_with(ARMOR_BEGIN.as_bytes()), armor);
+                for identity in [&alice, &bob] {
+                    let identity = identity.to_secret_string().unwrap().parse()?;
+                    let mut out = Vec::new();
+                    decrypt_stream(&sealed[..], &mut out, &[identity])?;
+                    assert_eq!(out, data);
+                }
+                let other = AgeIdentity::generate();
+                let err = decrypt_stream(&sealed[..], &mut Vec::new(), &[other]).unwrap_err();
+                assert!(matches!(err.downcast_ref(), Some(AgeError::NoMatch)));
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_age_scrypt_roundtrip() -> Result<()> {
+        let recipient = AgeRecipient::Scrypt {
+            passphrase: Zeroizing::new("correct horse".to_string()),
+            work_factor: 10,
+        };
+        let mut sealed = Vec::new();
+        encrypt_stream(&mut &b"hello age"[..], &mut sealed, &[recipient], true)?;
+
+        let identity = |p: &str, max_work_factor| AgeIdentity::Scrypt {
+            passphrase: Zeroizing::new(p.to_string()),
+            max_work_factor,
+        };
+        let mut out = Vec::new();
+        decrypt_stream(&sealed[..], &mut out, &[identity("correct horse", 10)])?;
+        assert_eq!(out, b"hello age");
+
+        let err = decrypt_stream(&sealed[..], &mut Vec::new(), &[identity("wrong", 10)]);
+        assert!(matches!(
+            err.unwrap_err().downcast_ref(),
+            Some(AgeError::NoMatch)
+        ));
+        let err = decrypt_stream(
+            &sealed[..],
+            &mut Vec::new(),
+            &[identity("correct horse", 9)],
+        );
+        assert!(matches!(
+            err.unwrap_err().downcast_ref(),
+            Some(AgeError::Header(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_age_scrypt_must_be_alone() {
+        let recipients = [
+            AgeIdentity::generate().to_recipient().unwrap(),
+            AgeRecipient::Scrypt {
+                passphrase: Zeroizing::new("pass".to_string()),
+                work_factor: 10,
+            },
+        ];
+        assert!(encrypt_stream(&mut &b""[..], &mut Vec::new(), &recipients, false).is_err());
+    }
+
+    #[test]
+    fn test_age_keys() -> Result<()> {
+        let (content, recipient) = process_age_keygen()?;
+        assert!(recipient.starts_with("age1"));
+        let identities: Vec<AgeIdentity> = key_lines(&content)
+            .map(|l| l.parse())
+            .collect::<Result<_>>()?;
+        assert_eq!(identities.len(), 1);
+        assert_eq!(identities[0].to_recipient().unwrap().to_string(), recipient);
+        assert_eq!(recipient.parse::<AgeRecipient>()?.to_string(), recipient);
+        assert!(recipient.to_uppercase().parse::<AgeIdentity>().is_err());
+        assert!(content
+            .lines()
+            .nth(1)
+            .unwrap()
+            .to_lowercase()
+            .parse::<AgeIdentity>()
+            .is_err());
+        Ok(())
+    }
+}
@@ -1,67 +1,385 @@
-use base64::{engine::general_purpose::URL_SAFE, prelude::BASE64_STANDARD, Engine as _};
+use std::io::{self, Read, Write};
 
-use crate::{cli::bas64_opts::Base64FormatType, get_reader};
+use anyhow::Context;
+use base64::{
+    alphabet,
+    engine::{
+        general_purpose::{STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD},
+        DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig,
+    },
+    prelude::BASE64_STANDARD,
+    read::DecoderReader,
+    write::EncoderWriter,
+};
 
-pub async fn process_encode(input: &str, format: Base64FormatType) -> anyhow::Result<String> {
-    let mut reader = get_reader(input)?;
+use crate::{cli::bas64_opts::Base64FormatType, get_reader, get_writer};
 
-    let mut buf = Vec::new();
-    reader.read_to_end(&mut buf)?;
+const MIME_LINE_LEN: usize = 76;
 
-    let encoded = match format {
-        Base64FormatType::STANDARD => BASE64_STANDARD.encode(buf),
-        Base64FormatType::UrlSafe => URL_SAFE.encode(buf),
-    };
-    println!("{}", encoded);
-    Ok(encoded)
+/// 宽松解码：输入已被统一为标准字母表，填充可有可无。
+const LENIENT: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new()
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent)
+        .with_decode_allow_trailing_bits(true),
+);
+
+fn engine(format: Base64FormatType) -> &'static GeneralPurpose {
+    match format {
+        Base64FormatType::STANDARD | Base64FormatType::Mime => &BASE64_STANDARD,
+        Base64FormatType::UrlSafe => &URL_SAFE,
+        Base64FormatType::StandardNoPad => &STANDARD_NO_PAD,
+        Base64FormatType::UrlSafeNoPad => &URL_SAFE_NO_PAD,
+        Base64FormatType::Lenient => &LENIENT,
+    }
 }
 
-pub async fn process_decode(input: &str, format: Base64FormatType) -> anyhow::Result<String> {
+pub async fn process_encode(
+    input: &str,
+    output: Option<&str>,
+    format: Base64FormatType,
+    wrap: usize,
+) -> anyhow::Result<()> {
     let mut reader = get_reader(input)?;
+    let mut writer = get_writer(output)?;
+    encode_stream(&mut reader, &mut writer, format, wrap)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub async fn process_decode(
+    input: &str,
+    output: Option<&str>,
+    format: Base64FormatType,
+    text: bool,
+) -> anyhow::Result<()> {
+    let mut reader = get_reader(input)?;
+    let mut writer = get_writer(output)?;
+    if text {
+        let mut buf = Vec::new();
+        decode_stream(&mut reader, &mut buf, format)?;
+        let text = String::from_utf8(buf).context("Decoded data is not valid UTF-8")?;
+        writeln!(writer, "{}", text)?;
+    } else {
+        decode_stream(&mut reader, &mut writer, format)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
 
-    let mut buf = String::new();
-    reader.read_to_string(&mut buf)?;
-    buf = buf.trim().to_string();
-    let decoded = match format {
-        Base64FormatType::UrlSafe => URL_SAFE.decode(buf)?,
-        Base64FormatType::STANDARD => BASE64_STANDARD.decode(buf)?,
+/// 以固定大小的缓冲区流式编码，`wrap` 为每行字符数（0 表示不换行），输出以换行结尾。
+/// MIME 格式默认每 76 个字符换行，并使用 CRLF。
+pub fn encode_stream<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    format: Base64FormatType,
+    wrap: usize,
+) -> anyhow::Result<u64> {
+    let (wrap, eol): (usize, &'static [u8]) = match format {
+        Base64FormatType::Mime if wrap == 0 => (MIME_LINE_LEN, b"\r\n"),
+        Base64FormatType::Mime => (wrap, b"\r\n"),
+        Base64FormatType::Lenient => {
+            anyhow::bail!("The lenient base64 format can only be used for decoding")
+        }
+        _ => (wrap, b"\n"),
     };
-    let decoded = String::from_utf8(decoded)?;
-    println!("{}", decoded);
+    let mut wrapper = LineWrapWriter::new(writer, wrap, eol);
+    let mut encoder = EncoderWriter::new(&mut wrapper, engine(format));
+    let n = io::copy(reader, &mut encoder)?;
+    encoder.finish()?.finish()?;
+    Ok(n)
+}
+
+/// 流式解码，输入中的空白字符（换行等）会被忽略。
+/// 宽松模式下会把 URL 安全字母表（`-`、`_`）转换为标准字母表，从而自动识别两种格式。
+pub fn decode_stream<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    format: Base64FormatType,
+) -> anyhow::Result<u64> {
+    let input = CleanInput::new(reader, format == Base64FormatType::Lenient);
+    let mut decoder = DecoderReader::new(input, engine(format));
+    let n = io::copy(&mut decoder, writer)?;
+    Ok(n)
+}
+
+pub(crate) struct LineWrapWriter<W: Write> {
+    inner: W,
+    wrap: usize,
+    eol: &'static [u8],
+    column: usize,
+}
+
+impl<W: Write> LineWrapWriter<W> {
+    pub(crate) fn new(inner: W, wrap: usize, eol: &'static [u8]) -> Self {
+        Self {
+            inner,
+            wrap,
+            eol,
+            column: 0,
+        }
+    }
+
+    pub(crate) fn finish(&mut self) -> io::Result<()> {
+        if self.wrap == 0 || self.column > 0 {
+            self.inner.write_all(self.eol)?;
+        }
+        self.column = 0;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Write for LineWrapWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.wrap == 0 {
+            return self.inner.write(buf);
+        }
+        let mut rest = buf;
+        while !rest.is_empty() {
+            if self.column == self.wrap {
+                self.inner.write_all(self.eol)?;
+                self.column = 0;
+            }
+            let n = rest.len().min(self.wrap - self.column);
+            self.inner.write_all(&rest[..n])?;
+            self.column += n;
+            rest = &rest[n..];
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+struct CleanInput<R: Read> {
+    inner: R,
+    normalize: bool,
+}
 
-    Ok(decoded)
+impl<R: Read> CleanInput<R> {
+    fn new(inner: R, normalize: bool) -> Self {
+        Self { inner, normalize }
+    }
+}
+
+impl<R: Read> Read for CleanInput<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.inner.read(buf)?;
+            if n == 0 {
+                return Ok(0);
+            }
+            let mut len = 0;
+            for i in 0..n {
+                let c = match buf[i] {
+                    c if c.is_ascii_whitespace() => continue,
+                    b'-' if self.normalize => b'+',
+                    b'_' if self.normalize => b'/',
+                    c => c,
+                };
+                buf[len] = c;
+                len += 1;
+            }
+            if len > 0 {
+                return Ok(len);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tokio::fs::{create_dir, File};
-    use tokio::io::AsyncWriteExt;
+    use base64::Engine as _;
+    use std::io::Cursor;
+    use tokio::fs::create_dir;
 
-    #[tokio::test]
     async fn test_process_encode() {
         let input = "Cargo.toml";
         let format = Base64FormatType::UrlSafe;
-        let encoded = process_encode(input, format).await.unwrap();
         let save_path = "fixtures/encode_urlsafe/Cargo_toml_b64.txt";
         if !std::path::Path::new("fixtures/encode_urlsafe").exists() {
             create_dir("fixtures/encode_urlsafe").await.unwrap();
         }
-        let mut file = File::create(save_path).await.unwrap();
-        let encoded = encoded + "\n";
-        file.write_all(encoded.as_bytes()).await.unwrap();
+        process_encode(input, Some(save_path), format, 0)
+            .await
+            .unwrap();
     }
 
-    #[tokio::test]
     async fn test_process_decode() {
         let input = "fixtures/encode_urlsafe/Cargo_toml_b64.txt";
         let format = Base64FormatType::UrlSafe;
-        let decoded = process_decode(input, format).await.unwrap();
         let save_path = "fixtures/decode_urlsafe/Cargo_toml.txt";
         if !std::path::Path::new("fixtures/decode_urlsafe").exists() {
             create_dir("fixtures/decode_urlsafe").await.unwrap();
         }
-        let mut file = File::create(save_path).await.unwrap();
-        file.write_all(decoded.as_bytes()).await.unwrap();
+        process_decode(input, Some(save_path), format, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read(save_path).unwrap(),
+            std::fs::read("Cargo.toml").unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_base64() {
+        test_process_encode().await;
+        test_process_decode().await;
+    }
+
+    #[test]
+    fn test_decode_binary() {
+        let expected = std::fs::read("fixtures/b64/binary.bin").unwrap();
+        assert!(String::from_utf8(expected.clone()).is_err());
+
+        for (path, format) in [
+            (
+                "fixtures/b64/binary_standard.b64",
+                Base64FormatType::STANDARD,
+            ),
+            ("fixtures/b64/binary_urlsafe.b64", Base64FormatType::UrlSafe),
+        ] {
+            let mut reader = std::fs::File::open(path).unwrap();
+            let mut decoded = Vec::new();
+            decode_stream(&mut reader, &mut decoded, format).unwrap();
+            assert_eq!(decoded, expected);
+        }
+    }
+
+    #[test]
+    fn test_encode_wrap_roundtrip() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7 % 256) as u8).collect();
+        let mut encoded = Vec::new();
+        encode_stream(
+            &mut Cursor::new(&data),
+            &mut encoded,
+            Base64FormatType::STANDARD,
+            76,
+        )
+        .unwrap();
+        let text = String::from_utf8(encoded.clone()).unwrap();
+        assert!(text.ends_with('\n'));
+        assert!(text.lines().all(|line| line.len() <= 76));
+        assert_eq!(text.lines().next().unwrap().len(), 76);
+        assert_eq!(
+            text.replace('\n', ""),
+            BASE64_STANDARD.encode(&data),
+            "wrapping must not change the encoded content"
+        );
+
+        let mut decoded = Vec::new();
+        decode_stream(
+            &mut Cursor::new(encoded),
+            &mut decoded,
+            Base64FormatType::STANDARD,
+        )
+        .unwrap();
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn test_encode_single_line() {
+        let mut encoded = Vec::new();
+        encode_stream(
+            &mut Cursor::new(b"hello world"),
+            &mut encoded,
+            Base64FormatType::UrlSafe,
+            0,
+        )
+        .unwrap();
+        assert_eq!(encoded, b"aGVsbG8gd29ybGQ=\n");
+    }
+
+    #[test]
+    fn test_encode_variants() {
+        let data = b"\xfb\xff\xfe hi";
+        let cases = [
+            (Base64FormatType::STANDARD, "+//+IGhp\n"),
+            (Base64FormatType::UrlSafe, "-__-IGhp\n"),
+            (Base64FormatType::StandardNoPad, "+//+IGhp\n"),
+            (Base64FormatType::UrlSafeNoPad, "-__-IGhp\n"),
+        ];
+        for (format, expected) in cases {
+            let mut encoded = Vec::new();
+            encode_stream(&mut Cursor::new(&data[..]), &mut encoded, format, 0).unwrap();
+            assert_eq!(String::from_utf8(encoded).unwrap(), expected, "{}", format);
+        }
+
+        let mut encoded = Vec::new();
+        encode_stream(
+            &mut Cursor::new(b"ab"),
+            &mut encoded,
+            Base64FormatType::UrlSafeNoPad,
+            0,
+        )
+        .unwrap();
+        assert_eq!(encoded, b"YWI\n");
+
+        let mut encoded = Vec::new();
+        assert!(encode_stream(
+            &mut Cursor::new(b"ab"),
+            &mut encoded,
+            Base64FormatType::Lenient,
+            0
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_mime_crlf() {
+        let data = vec![0u8; 100];
+        let mut encoded = Vec::new();
+        encode_stream(
+            &mut Cursor::new(&data),
+            &mut encoded,
+            Base64FormatType::Mime,
+            0,
+        )
+        .unwrap();
+        let text = String::from_utf8(encoded.clone()).unwrap();
+        let lines: Vec<&str> = text.split_terminator("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].len(), 76);
+        assert!(text.ends_with("\r\n"));
+
+        let mut decoded = Vec::new();
+        decode_stream(
+            &mut Cursor::new(encoded),
+            &mut decoded,
+            Base64FormatType::Mime,
+        )
+        .unwrap();
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn test_lenient_decode() {
+        let expected = b"\xfb\xff\xfe hi!";
+        for input in [
+            "+//+IGhpIQ==",
+            "-__-IGhpIQ==",
+            "-__-IGhpIQ",
+            "  +//+\r\n IGhp\tIQ=\n",
+        ] {
+            let mut decoded = Vec::new();
+            decode_stream(
+                &mut Cursor::new(input),
+                &mut decoded,
+                Base64FormatType::Lenient,
+            )
+            .unwrap();
+            assert_eq!(decoded, expected, "{:?}", input);
+        }
+
+        let mut decoded = Vec::new();
+        assert!(decode_stream(
+            &mut Cursor::new("-__-IGhpIQ"),
+            &mut decoded,
+            Base64FormatType::STANDARD
+        )
+        .is_err());
     }
 }
@@ -0,0 +1,462 @@
+use std::io::{self, Read, Write};
+
+use anyhow::{Context, Result};
+use percent_encoding::{
+    percent_decode, percent_decode_str, percent_encode, AsciiSet, NON_ALPHANUMERIC,
+};
+
+use crate::{cli::codec_opts::CodecFormat, get_reader, get_writer};
+
+/// RFC 3986 中除 unreserved 字符以外的所有字符都需要转义。
+pub const URI_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+const ASCII85_OFFSET: u8 = b'!';
+const Z85_ALPHABET: &[u8; 85] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+/// 编码时每次读取的字节数，是 base32（5 字节）与 base85（4 字节）分组长度的公倍数，
+/// 因此逐块编码的结果与一次性编码相同。
+const CHUNK_LEN: usize = 60 * 1024;
+
+pub async fn process_codec_encode(
+    input: &str,
+    output: Option<&str>,
+    format: CodecFormat,
+) -> Result<()> {
+    let mut reader = get_reader(input)?;
+    let mut writer = get_writer(output)?;
+    encode_stream(&mut reader, &mut writer, format)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub async fn process_codec_decode(
+    input: &str,
+    output: Option<&str>,
+    format: CodecFormat,
+    text: bool,
+) -> Result<()> {
+    let mut reader = get_reader(input)?;
+    let mut writer = get_writer(output)?;
+    if text {
+        let mut buf = Vec::new();
+        decode_stream(&mut reader, &mut buf, format)?;
+        let text = String::from_utf8(buf).context("Decoded data is not valid UTF-8")?;
+        writeln!(writer, "{}", text)?;
+    } else {
+        decode_stream(&mut reader, &mut writer, format)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// 按 `CHUNK_LEN` 分块编码，输出以换行结尾。base58 是整体的大数转换，只能把输入整个读入内存。
+pub fn encode_stream<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    format: CodecFormat,
+) -> Result<()> {
+    if format == CodecFormat::Base58 {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+        writer.write_all(encode_bytes(&buf, format)?.as_bytes())?;
+    } else {
+        let mut buf = vec![0u8; CHUNK_LEN];
+        loop {
+            let n = read_full(reader, &mut buf)?;
+            if n > 0 {
+                writer.write_all(encode_bytes(&buf[..n], format)?.as_bytes())?;
+            }
+            if n < buf.len() {
+                break;
+            }
+        }
+    }
+    writeln!(writer)?;
+    Ok(())
+}
+
+/// 流式解码：去掉空白后按完整的分组（hex 2、base32 8、z85 5 个字符）逐块解码，
+/// 百分号编码保留可能被截断的转义序列。base58 与 ascii85 会把输入整个读入内存。
+pub fn decode_stream<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    format: CodecFormat,
+) -> Result<()> {
+    let group = match format {
+        CodecFormat::Hex | CodecFormat::HexUpper => 2,
+        CodecFormat::Base32 | CodecFormat::Base32Crockford => 8,
+        CodecFormat::Z85 => 5,
+        CodecFormat::Percent => return decode_percent_stream(reader, writer),
+        CodecFormat::Base58 | CodecFormat::Ascii85 => {
+            let mut buf = String::new();
+            reader.read_to_string(&mut buf)?;
+            writer.write_all(&decode_str(&buf, format)?)?;
+            return Ok(());
+        }
+    };
+    let mut buf = vec![0u8; CHUNK_LEN];
+    let mut pending = Vec::new();
+    loop {
+        let n = reader.read(&mut buf)?;
+        pending.extend(buf[..n].iter().filter(|c| !c.is_ascii_whitespace()));
+        let ready = if n == 0 {
+            pending.len()
+        } else {
+            pending.len() / group * group
+        };
+        if ready > 0 {
+            let input = std::str::from_utf8(&pending[..ready])
+                .with_context(|| format!("Invalid {} input", format))?;
+            writer.write_all(&decode_str(input, format)?)?;
+            pending.drain(..ready);
+        }
+        if n == 0 {
+            return Ok(());
+        }
+    }
+}
+
+/// 去掉首尾空白后逐块解码，末尾的空白与不完整的 `%XX` 留到下一块。
+fn decode_percent_stream<R: Read, W: Write>(reader: &mut R, writer: &mut W) -> Result<()> {
+    let mut buf = vec![0u8; CHUNK_LEN];
+    let mut pending: Vec<u8> = Vec::new();
+    let mut started = false;
+    loop {
+        let n = reader.read(&mut buf)?;
+        pending.extend_from_slice(&buf[..n]);
+        if !started {
+            let skip = pending
+                .iter()
+                .take_while(|c| c.is_ascii_whitespace())
+                .count();
+            pending.drain(..skip);
+            started = !pending.is_empty();
+        }
+        let mut ready = pending.len()
+            - pending
+                .iter()
+                .rev()
+                .take_while(|c| c.is_ascii_whitespace())
+                .count();
+        if n > 0 {
+            let tail = ready.saturating_sub(2);
+            if let Some(i) = pending[tail..ready].iter().position(|&c| c == b'%') {
+                ready = tail + i;
+            }
+        }
+        let decoded: Vec<u8> = percent_decode(&pending[..ready]).collect();
+        writer.write_all(&decoded)?;
+        pending.drain(..ready);
+        if n == 0 {
+            return Ok(());
+        }
+    }
+}
+
+/// 读满 `buf` 或读到输入结束，返回读取的字节数。
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(len)
+}
+
+pub fn encode_bytes(data: &[u8], format: CodecFormat) -> Result<String> {
+    let encoded = match format {
+        CodecFormat::Hex => hex::encode(data),
+        CodecFormat::HexUpper => hex::encode_upper(data),
+        CodecFormat::Base32 => base32::encode(base32::Alphabet::Rfc4648 { padding: true }, data),
+        CodecFormat::Base32Crockford => base32::encode(base32::Alphabet::Crockford, data),
+        CodecFormat::Base58 => bs58::encode(data).into_string(),
+        CodecFormat::Ascii85 => ascii85_encode(data),
+        CodecFormat::Z85 => z85_encode(data)?,
+        CodecFormat::Percent => percent_encode(data, URI_COMPONENT).to_string(),
+    };
+    Ok(encoded)
+}
+
+/// 解码前会去掉空白字符（百分号编码只去掉首尾空白）。
+pub fn decode_str(input: &str, format: CodecFormat) -> Result<Vec<u8>> {
+    let decoded = match format {
+        CodecFormat::Hex | CodecFormat::HexUpper => hex::decode(strip_whitespace(input))?,
+        CodecFormat::Base32 => base32::decode(
+            base32::Alphabet::Rfc4648 { padding: true },
+            &strip_whitespace(input).to_uppercase(),
+        )
+        .context("Invalid base32 input")?,
+        CodecFormat::Base32Crockford => {
+            base32::decode(base32::Alphabet::Crockford, &strip_whitespace(input))
+                .context("Invalid base32 input")?
+        }
+        CodecFormat::Base58 => bs58::decode(strip_whitespace(input)).into_vec()?,
+        CodecFormat::Ascii85 => ascii85_decode(&strip_whitespace(input))?,
+        CodecFormat::Z85 => z85_decode(&strip_whitespace(input))?,
+        CodecFormat::Percent => percent_decode_str(input.trim()).collect(),
+    };
+    Ok(decoded)
+}
+
+fn strip_whitespace(input: &str) -> String {
+    input.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// 将 4 字节大端整数拆分为 5 个 base85 数字。
+fn base85_digits(mut value: u32) -> [u8; 5] {
+    let mut digits = [0u8; 5];
+    for digit in digits.iter_mut().rev() {
+        *digit = (value % 85) as u8;
+        value /= 85;
+    }
+    digits
+}
+
+fn base85_value(digits: &[u8; 5]) -> Option<u32> {
+    digits
+        .iter()
+        .try_fold(0u32, |acc, &d| acc.checked_mul(85)?.checked_add(d as u32))
+}
+
+/// Adobe / btoa 风格的 Ascii85：全零分组压缩为 `z`，末尾不足 4 字节的分组输出 n + 1 个字符。
+fn ascii85_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len() * 5 / 4 + 5);
+    for chunk in data.chunks(4) {
+        let mut group = [0u8; 4];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let value = u32::from_be_bytes(group);
+        if chunk.len() == 4 && value == 0 {
+            out.push('z');
+            continue;
+        }
+        let digits = base85_digits(value);
+        for d in &digits[..chunk.len() + 1] {
+            out.push((d + ASCII85_OFFSET) as char);
+        }
+    }
+    out
+}
+
+fn ascii85_decode(input: &str) -> Result<Vec<u8>> {
+    let input = input.strip_prefix("<~").unwrap_or(input);
+    let input = input.strip_suffix("~>").unwrap_or(input);
+
+    let mut out = Vec::with_capacity(input.len() * 4 / 5 + 4);
+    let mut group = [0u8; 5];
+    let mut len = 0;
+    for (i, c) in input.bytes().enumerate() {
+        match c {
+            b'z' if len == 0 => out.extend_from_slice(&[0u8; 4]),
+            b'!'..=b'u' => {
+                group[len] = c - ASCII85_OFFSET;
+                len += 1;
+                if len == 5 {
+                    let value = base85_value(&group).context("Invalid ascii85 group")?;
+                    out.extend_from_slice(&value.to_be_bytes());
+                    len = 0;
+                }
+            }
+            _ => anyhow::bail!("Invalid ascii85 character {:?} at {}", c as char, i),
+        }
+    }
+    if len == 1 {
+        anyhow::bail!("Invalid ascii85 input: dangling final character");
+    }
+    if len > 0 {
+        group[len..].fill(b'u' - ASCII85_OFFSET);
+        let value = base85_value(&group).context("Invalid ascii85 group")?;
+        out.extend_from_slice(&value.to_be_bytes()[..len - 1]);
+    }
+    Ok(out)
+}
+
+/// ZeroMQ Z85（RFC 32/Z85）：输入长度必须是 4 的倍数。
+fn z85_encode(data: &[u8]) -> Result<String> {
+    if !data.len().is_multiple_of(4) {
+        anyhow::bail!(
+            "Z85 input length must be a multiple of 4 bytes, got {}",
+            data.len()
+        );
+    }
+    let mut out = String::with_capacity(data.len() * 5 / 4);
+    for chunk in data.chunks(4) {
+        let value = u32::from_be_bytes(chunk.try_into()?);
+        for d in base85_digits(value) {
+            out.push(Z85_ALPHABET[d as usize] as char);
+        }
+    }
+    Ok(out)
+}
+
+fn z85_decode(input: &str) -> Result<Vec<u8>> {
+    if !input.len().is_multiple_of(5) {
+        anyhow::bail!(
+            "Z85 input length must be a multiple of 5 characters, got {}",
+            input.len()
+        );
+    }
+    let mut out = Vec::with_capacity(input.len() * 4 / 5);
+    for chunk in input.as_bytes().chunks(5) {
+        let mut group = [0u8; 5];
+        for (digit, c) in group.iter_mut().zip(chunk) {
+            *digit = Z85_ALPHABET
+                .iter()
+                .position(|a| a == c)
+                .with_context(|| format!("Invalid Z85 character {:?}", *c as char))?
+                as u8;
+        }
+        let value = base85_value(&group).context("Invalid Z85 group")?;
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_FORMATS: [CodecFormat; 8] = [
+        CodecFormat::Hex,
+        CodecFormat::HexUpper,
+        CodecFormat::Base32,
+        CodecFormat::Base32Crockford,
+        CodecFormat::Base58,
+        CodecFormat::Ascii85,
+        CodecFormat::Z85,
+        CodecFormat::Percent,
+    ];
+
+    #[test]
+    fn test_known_vectors() {
+        let cases = [
+            (CodecFormat::Hex, &b"\x00\xffrcli"[..], "00ff72636c69"),
+            (CodecFormat::HexUpper, b"\x00\xffrcli", "00FF72636C69"),
+            (CodecFormat::Base32, b"foobar", "MZXW6YTBOI======"),
+            (CodecFormat::Base32Crockford, b"foobar", "CSQPYRK1E8"),
+            (
+                CodecFormat::Base58,
+                b"\x00\x00hello world",
+                "11StV1DL6CwTryKyV",
+            ),
+            (CodecFormat::Ascii85, b"Man is", "9jqo^Bla"),
+            (CodecFormat::Ascii85, b"\0\0\0\0\0", "z!!"),
+            (
+                CodecFormat::Z85,
+                b"\x86\x4F\xD2\x6F\xB5\x59\xF7\x5B",
+                "HelloWorld",
+            ),
+            (
+                CodecFormat::Percent,
+                "a b/c?d=é".as_bytes(),
+                "a%20b%2Fc%3Fd%3D%C3%A9",
+            ),
+        ];
+        for (format, data, encoded) in cases {
+            assert_eq!(encode_bytes(data, format).unwrap(), encoded, "{}", format);
+            assert_eq!(decode_str(encoded, format).unwrap(), data, "{}", format);
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_binary() {
+        let data = std::fs::read("fixtures/b64/binary.bin").unwrap();
+        let data = &data[..data.len() / 4 * 4];
+        for format in ALL_FORMATS {
+            let encoded = encode_bytes(data, format).unwrap();
+            assert_eq!(decode_str(&encoded, format).unwrap(), data, "{}", format);
+        }
+    }
+
+    /// 每次只返回一个字节，覆盖分组与转义序列被截断的情况。
+    struct ByteReader<'a>(&'a [u8]);
+
+    impl Read for ByteReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.split_first() {
+                Some((&c, rest)) if !buf.is_empty() => {
+                    buf[0] = c;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_stream_matches_one_shot() {
+        // 超过 CHUNK_LEN，且长度是 4 的倍数（z85）
+        let data: Vec<u8> = (0..CHUNK_LEN * 2 + 1000)
+            .map(|i| (i * 31 % 251) as u8)
+            .collect();
+        for format in ALL_FORMATS {
+            if format == CodecFormat::Base58 {
+                continue;
+            }
+            let mut encoded = Vec::new();
+            encode_stream(&mut &data[..], &mut encoded, format).unwrap();
+            let expected = encode_bytes(&data, format).unwrap();
+            assert_eq!(encoded, format!("{}\n", expected).as_bytes(), "{}", format);
+
+            let mut decoded = Vec::new();
+            decode_stream(&mut &encoded[..], &mut decoded, format).unwrap();
+            assert_eq!(decoded, data, "{}", format);
+        }
+
+        for (format, input, expected) in [
+            (CodecFormat::Hex, " 00 ff\n72 ", &b"\x00\xffr"[..]),
+            (CodecFormat::Base32, "MZXW6\nYTBOI======\n", b"foobar"),
+            (
+                CodecFormat::Z85,
+                "Hello\nWorld",
+                b"\x86\x4F\xD2\x6F\xB5\x59\xF7\x5B",
+            ),
+            (
+                CodecFormat::Percent,
+                "  a%20b%2F c%C3%A9 \n",
+                "a b/ cé".as_bytes(),
+            ),
+            (CodecFormat::Percent, "100%", b"100%"),
+        ] {
+            let mut decoded = Vec::new();
+            decode_stream(&mut ByteReader(input.as_bytes()), &mut decoded, format).unwrap();
+            assert_eq!(decoded, expected, "{}", format);
+        }
+        let mut decoded = Vec::new();
+        assert!(decode_stream(&mut ByteReader(b"abc"), &mut decoded, CodecFormat::Hex).is_err());
+    }
+
+    #[test]
+    fn test_decode_tolerates_formatting() {
+        assert_eq!(
+            decode_str("00 FF\n72", CodecFormat::Hex).unwrap(),
+            b"\x00\xffr"
+        );
+        assert_eq!(
+            decode_str("mzxw6ytboi======\n", CodecFormat::Base32).unwrap(),
+            b"foobar"
+        );
+        assert_eq!(
+            decode_str("<~9jqo^\nBla~>", CodecFormat::Ascii85).unwrap(),
+            b"Man is"
+        );
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert!(encode_bytes(b"abc", CodecFormat::Z85).is_err());
+        assert!(decode_str("Hello", CodecFormat::Z85).is_ok());
+        assert!(decode_str("Hell", CodecFormat::Z85).is_err());
+        assert!(decode_str("0OIl", CodecFormat::Base58).is_err());
+        assert!(decode_str("abc", CodecFormat::Hex).is_err());
+        assert!(decode_str("9jqo~", CodecFormat::Ascii85).is_err());
+    }
+}
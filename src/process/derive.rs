@@ -0,0 +1,180 @@
+use anyhow::Result;
+use hkdf::Hkdf;
+use rand::rngs::OsRng;
+use sha2::Sha256;
+use x25519_dalek::{PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+use super::{
+    key::{KeyFile, KeyKind},
+    text::KeyGenerator,
+};
+
+/// HKDF 的 info 前缀，`--context` 追加在其后，不同用途的密钥互不相同。
+const DERIVE_INFO: &[u8] = b"rcli text derive chacha20-poly1305";
+
+pub(super) struct X25519;
+
+impl KeyGenerator for X25519 {
+    fn generate() -> Result<Vec<Vec<u8>>> {
+        let secret = StaticSecret::random_from_rng(OsRng);
+        let public = PublicKey::from(&secret);
+        Ok(vec![secret.to_bytes().to_vec(), public.as_bytes().to_vec()])
+    }
+}
+
+/// 用我方的 X25519 私钥与对方的公钥协商出 ChaCha20-Poly1305 密钥：
+/// ECDH 的共享秘密经 HKDF-SHA256 派生，盐为双方公钥按字节序排列后的拼接，
+/// 因此双方各自计算得到相同的密钥，`context` 不同时得到不同的密钥。
+pub fn process_derive(key: &str, peer: &str, context: &[u8]) -> Result<KeyFile> {
+    let secret = KeyFile::load(key)?.key_for(KeyKind::X25519Private)?;
+    let peer = KeyFile::load(peer)?.key_for(KeyKind::X25519Public)?;
+    let key = derive_key(
+        &StaticSecret::from(*secret),
+        &PublicKey::from(*peer),
+        context,
+    )?;
+    KeyFile::new(KeyKind::ChaCha20, &key[..])
+}
+
+fn derive_key(
+    secret: &StaticSecret,
+    peer: &PublicKey,
+    context: &[u8],
+) -> Result<Zeroizing<[u8; 32]>> {
+    let public = PublicKey::from(secret);
+    if public == *peer {
+        anyhow::bail!("The peer key is our own public key, pass the other party's x25519.pub");
+    }
+    let shared = secret.diffie_hellman(peer);
+    if !shared.was_contributory() {
+        anyhow::bail!("Invalid peer key: low-order x25519 public key");
+    }
+    let (first, second) = if public.as_bytes() < peer.as_bytes() {
+        (public, *peer)
+    } else {
+        (*peer, public)
+    };
+    let mut salt = first.as_bytes().to_vec();
+    salt.extend_from_slice(second.as_bytes());
+    let mut info = DERIVE_INFO.to_vec();
+    info.extend_from_slice(context);
+
+    let mut okm = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(Some(&salt), shared.as_bytes())
+        .expand(&info, okm.as_mut())
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    Ok(okm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::text::KeyEncoding;
+
+    fn key_pair() -> (StaticSecret, PublicKey) {
+        let keys = X25519::generate().unwrap();
+        let secret: [u8; 32] = keys[0][..].try_into().unwrap();
+        let public: [u8; 32] = keys[1][..].try_into().unwrap();
+        (StaticSecret::from(secret), PublicKey::from(public))
+    }
+
+    #[test]
+    fn test_rfc7748_shared_secret() {
+        // RFC 7748 6.1
+        let alice = StaticSecret::from(
+            <[u8; 32]>::try_from(
+                hex::decode("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a")
+                    .unwrap(),
+            )
+            .unwrap(),
+        );
+        let bob = PublicKey::from(
+            <[u8; 32]>::try_from(
+                hex::decode("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f")
+                    .unwrap(),
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            hex::encode(alice.diffie_hellman(&bob).as_bytes()),
+            "4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742"
+        );
+    }
+
+    #[test]
+    fn test_both_sides_derive_the_same_key() -> Result<()> {
+        let (alice, alice_public) = key_pair();
+        let (bob, bob_public) = key_pair();
+        let key = derive_key(&alice, &bob_public, b"")?;
+        assert_eq!(*derive_key(&bob, &alice_public, b"")?, *key);
+        assert_ne!(*derive_key(&bob, &alice_public, b"backup")?, *key);
+
+        let (_, eve_public) = key_pair();
+        assert_ne!(*derive_key(&alice, &eve_public, b"")?, *key);
+        Ok(())
+    }
+
+    #[test]
+    fn test_derived_key_encrypts() -> Result<()> {
+        use crate::{
+            cli::text::EncryptionAlgorithm, process_decrypt, process_encrypt, EncryptionKey,
+        };
+        use std::fs;
+
+        let dir = std::env::temp_dir().join(format!("rcli-derive-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+        for name in ["alice", "bob"] {
+            let keys = X25519::generate()?;
+            let private = KeyFile::new(KeyKind::X25519Private, &keys[0])?;
+            let public = KeyFile::new(KeyKind::X25519Public, &keys[1])?;
+            fs::write(
+                path(&format!("{}.priv", name)),
+                private.encode(KeyEncoding::Pem)?,
+            )?;
+            fs::write(
+                path(&format!("{}.pub", name)),
+                public.encode(KeyEncoding::Raw)?,
+            )?;
+        }
+        let alice = process_derive(&path("alice.priv"), &path("bob.pub"), b"")?;
+        let bob = process_derive(&path("bob.priv"), &path("alice.pub"), b"")?;
+        fs::write(path("alice.key"), alice.encode(KeyEncoding::Raw)?)?;
+        fs::write(path("bob.key"), bob.encode(KeyEncoding::Hex)?)?;
+        fs::write(path("message"), b"meet at noon")?;
+        process_encrypt(
+            &path("message"),
+            Some(&path("message.enc")),
+            EncryptionKey::File(path("alice.key")),
+            EncryptionAlgorithm::XChaCha20Poly1305,
+            b"",
+        )?;
+        process_decrypt(
+            &path("message.enc"),
+            Some(&path("message.dec")),
+            EncryptionKey::File(path("bob.key")),
+            b"",
+        )?;
+        assert_eq!(fs::read(path("message.dec"))?, b"meet at noon");
+
+        // 用错密钥文件时给出明确的错误
+        let err = process_derive(&path("alice.pub"), &path("bob.pub"), b"")
+            .err()
+            .unwrap();
+        assert!(
+            format!("{:#}", err).contains("x25519 private key"),
+            "{:#}",
+            err
+        );
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_invalid_peer() {
+        let (alice, alice_public) = key_pair();
+        assert!(derive_key(&alice, &alice_public, b"").is_err());
+        assert!(derive_key(&alice, &PublicKey::from([0u8; 32]), b"").is_err());
+    }
+}
@@ -0,0 +1,1026 @@
+use std::{
+    fs,
+    io::{self, Read, Write},
+    ops::Sub,
+};
+
+use anyhow::{Context, Result};
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{
+        generic_array::{typenum::U5, ArrayLength, GenericArray},
+        rand_core::RngCore,
+        stream::{DecryptorBE32, EncryptorBE32},
+        Aead, AeadInPlace, KeyInit, Nonce, OsRng, Payload,
+    },
+    AeadCore, ChaCha20Poly1305, XChaCha20Poly1305,
+};
+use zeroize::Zeroizing;
+
+use super::{
+    key::{KeyFile, KeyKind},
+    text::{KeyGenerator, KeyLoader},
+};
+use crate::{cli::text::EncryptionAlgorithm, get_reader, get_writer};
+
+const MAGIC: &[u8; 4] = b"RCLI";
+const VERSION: u8 = 1;
+/// 口令加密的密文在算法之后多了一个 KDF 参数块。
+const VERSION_PASSPHRASE: u8 = 2;
+/// 分段（STREAM）格式，见 `StreamHeader`。
+const VERSION_STREAM: u8 = 3;
+const KDF_NONE: u8 = 0;
+const KDF_ARGON2ID: u8 = 1;
+/// 分段格式中每段明文的长度，最后一段可以更短。
+pub const SEGMENT_SIZE: usize = 64 * 1024;
+const TAG_LEN: usize = 16;
+const SALT_LEN: usize = 16;
+/// 解密时拒绝超出该上限的代价参数，避免恶意文件耗尽内存或 CPU。
+const MAX_M_COST: u32 = 2 * 1024 * 1024;
+const MAX_T_COST: u32 = 64;
+const MAX_P_COST: u32 = 16;
+
+/// 加密密钥的来源。
+pub enum EncryptionKey {
+    /// `text generate --format base64` 生成的密钥文件。
+    File(String),
+    /// 口令，通过 Argon2id 派生出 256 位密钥。
+    Passphrase(Zeroizing<String>),
+}
+
+/// `aad` 为调用方提供的关联数据，必须与加密时一致。
+pub trait TextDecrypt {
+    fn decrypt(&self, envelope: &Envelope, aad: &[u8]) -> Result<Vec<u8>>;
+}
+
+impl EncryptionAlgorithm {
+    fn id(&self) -> u8 {
+        match self {
+            EncryptionAlgorithm::ChaCha20Poly1305 => 1,
+            EncryptionAlgorithm::XChaCha20Poly1305 => 2,
+        }
+    }
+
+    fn nonce_len(&self) -> usize {
+        match self {
+            EncryptionAlgorithm::ChaCha20Poly1305 => 12,
+            EncryptionAlgorithm::XChaCha20Poly1305 => 24,
+        }
+    }
+}
+
+impl TryFrom<u8> for EncryptionAlgorithm {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            1 => Ok(EncryptionAlgorithm::ChaCha20Poly1305),
+            2 => Ok(EncryptionAlgorithm::XChaCha20Poly1305),
+            v => anyhow::bail!("Unsupported encryption algorithm id: {}", v),
+        }
+    }
+}
+
+/// Argon2id 参数，随密文一起保存，解密时用相同的参数重新派生密钥。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Argon2Kdf {
+    salt: [u8; SALT_LEN],
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+}
+
+impl Argon2Kdf {
+    /// 随机盐，代价参数使用 argon2 的默认值（19 MiB、2 轮、1 线程）。
+    pub fn generate() -> Self {
+        let params = argon2::Params::default();
+        Self::with_params(params.m_cost(), params.t_cost(), params.p_cost())
+    }
+
+    pub fn with_params(m_cost: u32, t_cost: u32, p_cost: u32) -> Self {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self {
+            salt,
+            m_cost,
+            t_cost,
+            p_cost,
+        }
+    }
+
+    pub fn derive(&self, passphrase: &[u8]) -> Result<Zeroizing<[u8; 32]>> {
+        if self.m_cost > MAX_M_COST || self.t_cost > MAX_T_COST || self.p_cost > MAX_P_COST {
+            anyhow::bail!(
+                "Argon2 parameters are too expensive: m={} KiB, t={}, p={}",
+                self.m_cost,
+                self.t_cost,
+                self.p_cost
+            );
+        }
+        let params = argon2::Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .map_err(|e| anyhow::anyhow!("Invalid Argon2 parameters: {}", e))?;
+        let argon2 = Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+        let mut key = Zeroizing::new([0u8; 32]);
+        argon2
+            .hash_password_into(passphrase, &self.salt, key.as_mut())
+            .map_err(|e| anyhow::anyhow!("Failed to derive key: {}", e))?;
+        Ok(key)
+    }
+
+    pub(super) fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.push(KDF_ARGON2ID);
+        buf.extend_from_slice(&self.m_cost.to_be_bytes());
+        buf.extend_from_slice(&self.t_cost.to_be_bytes());
+        buf.extend_from_slice(&self.p_cost.to_be_bytes());
+        buf.extend_from_slice(&self.salt);
+    }
+
+    /// 读取 KDF 标识及其参数，`KDF_NONE` 表示使用密钥文件。
+    pub(super) fn read_from<R: Read>(reader: &mut R) -> Result<Option<Self>> {
+        let mut id = [0u8; 1];
+        read_header(reader, &mut id)?;
+        match id[0] {
+            KDF_NONE => return Ok(None),
+            KDF_ARGON2ID => {}
+            v => anyhow::bail!("Unsupported key derivation function id: {}", v),
+        }
+        let mut params = [0u8; 12];
+        read_header(reader, &mut params)?;
+        let mut salt = [0u8; SALT_LEN];
+        read_header(reader, &mut salt)?;
+        let cost = |i: usize| u32::from_be_bytes(params[i * 4..i * 4 + 4].try_into().unwrap());
+        Ok(Some(Self {
+            salt,
+            m_cost: cost(0),
+            t_cost: cost(1),
+            p_cost: cost(2),
+        }))
+    }
+}
+
+fn read_header<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<()> {
+    reader.read_exact(buf).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => anyhow::anyhow!("Envelope is truncated"),
+        _ => e.into(),
+    })
+}
+
+/// 整体加密的旧格式，新的密文均使用分段格式（见 `StreamHeader`），这里只用于解密：
+///
+/// ```text
+/// 密钥文件：magic "RCLI" (4) | version 1 | algorithm (1) | nonce | ciphertext + tag
+/// 口令：    magic "RCLI" (4) | version 2 | algorithm (1)
+///           | kdf (1) | m_cost (4) | t_cost (4) | p_cost (4) | salt (16) | nonce | ciphertext + tag
+/// ```
+///
+/// 头部（magic 到 nonce）与用户提供的 `--aad` 一起作为 AEAD 的关联数据参与认证，
+/// 任何篡改都会导致解密失败。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    pub algorithm: EncryptionAlgorithm,
+    pub kdf: Option<Argon2Kdf>,
+    pub nonce: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
+
+impl Envelope {
+    fn header(&self) -> Vec<u8> {
+        let mut header = Vec::with_capacity(MAGIC.len() + 2 + 29 + self.nonce.len());
+        header.extend_from_slice(MAGIC);
+        match &self.kdf {
+            None => {
+                header.push(VERSION);
+                header.push(self.algorithm.id());
+            }
+            Some(kdf) => {
+                header.push(VERSION_PASSPHRASE);
+                header.push(self.algorithm.id());
+                kdf.write_to(&mut header);
+            }
+        }
+        header.extend_from_slice(&self.nonce);
+        header
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < MAGIC.len() + 2 || &bytes[..MAGIC.len()] != MAGIC {
+            anyhow::bail!("Input is not an rcli encrypted envelope");
+        }
+        let version = bytes[MAGIC.len()];
+        let algorithm = EncryptionAlgorithm::try_from(bytes[MAGIC.len() + 1])?;
+        let mut rest = &bytes[MAGIC.len() + 2..];
+        let kdf = match version {
+            VERSION => None,
+            VERSION_PASSPHRASE => Some(
+                Argon2Kdf::read_from(&mut rest)?
+                    .context("Passphrase envelope is missing its KDF parameters")?,
+            ),
+            VERSION_STREAM => anyhow::bail!("Chunked envelopes must be decrypted as a stream"),
+            v => anyhow::bail!("Unsupported envelope version: {}", v),
+        };
+        if rest.len() < algorithm.nonce_len() {
+            anyhow::bail!("Envelope is truncated");
+        }
+        let (nonce, ciphertext) = rest.split_at(algorithm.nonce_len());
+        Ok(Self {
+            algorithm,
+            kdf,
+            nonce: nonce.to_vec(),
+            ciphertext: ciphertext.to_vec(),
+        })
+    }
+
+    fn aad(&self, aad: &[u8]) -> Vec<u8> {
+        let mut header = self.header();
+        header.extend_from_slice(aad);
+        header
+    }
+}
+
+/// 分段（STREAM）格式的头部：
+///
+/// ```text
+/// magic "RCLI" (4) | version 3 | algorithm (1) | kdf (1) [| m_cost | t_cost | p_cost | salt] | nonce 前缀
+/// ```
+///
+/// 之后是若干 `SEGMENT_SIZE + 16` 字节的密文段，最后一段可以更短（至少 16 字节的 tag）。
+/// 每段的 nonce 为 `前缀 || 32 位大端计数器 || 最后一段标志`，因此分段被截断、重排或删除时都会解密失败。
+/// 头部与 `--aad` 作为每一段的关联数据。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamHeader {
+    pub algorithm: EncryptionAlgorithm,
+    pub kdf: Option<Argon2Kdf>,
+    pub nonce: Vec<u8>,
+}
+
+impl StreamHeader {
+    fn nonce_len(algorithm: EncryptionAlgorithm) -> usize {
+        // 5 字节留给计数器和最后一段标志
+        algorithm.nonce_len() - 5
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut header = Vec::with_capacity(MAGIC.len() + 3 + 28 + self.nonce.len());
+        header.extend_from_slice(MAGIC);
+        header.push(VERSION_STREAM);
+        header.push(self.algorithm.id());
+        match &self.kdf {
+            None => header.push(KDF_NONE),
+            Some(kdf) => kdf.write_to(&mut header),
+        }
+        header.extend_from_slice(&self.nonce);
+        header
+    }
+
+    /// 读取 magic、版本号与算法之后的部分。
+    fn read_body<R: Read>(algorithm: EncryptionAlgorithm, reader: &mut R) -> Result<Self> {
+        let kdf = Argon2Kdf::read_from(reader)?;
+        let mut nonce = vec![0u8; Self::nonce_len(algorithm)];
+        read_header(reader, &mut nonce)?;
+        Ok(Self {
+            algorithm,
+            kdf,
+            nonce,
+        })
+    }
+
+    fn aad(&self, aad: &[u8]) -> Vec<u8> {
+        let mut header = self.to_bytes();
+        header.extend_from_slice(aad);
+        header
+    }
+}
+
+pub(crate) struct ChaCha20 {
+    key: Zeroizing<[u8; 32]>,
+    kdf: Option<Argon2Kdf>,
+}
+
+impl ChaCha20 {
+    pub fn new(key: [u8; 32]) -> Self {
+        Self {
+            key: Zeroizing::new(key),
+            kdf: None,
+        }
+    }
+
+    /// 用口令派生密钥，加密时会把 `kdf` 参数写入密文头部。
+    pub fn from_passphrase(passphrase: &str, kdf: Argon2Kdf) -> Result<Self> {
+        let key = kdf.derive(passphrase.as_bytes())?;
+        Ok(Self {
+            key,
+            kdf: Some(kdf),
+        })
+    }
+}
+
+fn open<C: Aead + AeadCore + KeyInit>(
+    key: &[u8; 32],
+    envelope: &Envelope,
+    aad: &[u8],
+) -> Result<Vec<u8>> {
+    let cipher = C::new_from_slice(key).map_err(|e| anyhow::anyhow!(e))?;
+    cipher
+        .decrypt(
+            Nonce::<C>::from_slice(&envelope.nonce),
+            Payload {
+                msg: &envelope.ciphertext,
+                aad: &envelope.aad(aad),
+            },
+        )
+        .map_err(|_| {
+            anyhow::anyhow!(
+                "Decryption failed: wrong key or passphrase, wrong --aad or corrupted data"
+            )
+        })
+}
+
+/// 尽量读满 `buf`，只有到达输入末尾时才返回较短的长度。
+fn read_segment<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// 多读一段以判断当前段是否为最后一段，内存占用固定为两段。
+pub(super) fn seal_stream<C, R: Read, W: Write>(
+    key: &[u8; 32],
+    nonce: &[u8],
+    aad: &[u8],
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<u64>
+where
+    C: AeadInPlace + KeyInit,
+    C::NonceSize: Sub<U5>,
+    <C::NonceSize as Sub<U5>>::Output: ArrayLength<u8>,
+{
+    let cipher = C::new_from_slice(key).map_err(|e| anyhow::anyhow!(e))?;
+    let mut encryptor = EncryptorBE32::from_aead(cipher, GenericArray::from_slice(nonce));
+    let mut current = vec![0u8; SEGMENT_SIZE];
+    let mut next = vec![0u8; SEGMENT_SIZE];
+    let mut len = read_segment(reader, &mut current)?;
+    let mut total = 0;
+    loop {
+        let next_len = read_segment(reader, &mut next)?;
+        if next_len == 0 {
+            break;
+        }
+        let segment = encryptor
+            .encrypt_next(Payload {
+                msg: &current[..len],
+                aad,
+            })
+            .map_err(|e| anyhow::anyhow!(e))?;
+        writer.write_all(&segment)?;
+        total += len as u64;
+        std::mem::swap(&mut current, &mut next);
+        len = next_len;
+    }
+    let segment = encryptor
+        .encrypt_last(Payload {
+            msg: &current[..len],
+            aad,
+        })
+        .map_err(|e| anyhow::anyhow!(e))?;
+    writer.write_all(&segment)?;
+    Ok(total + len as u64)
+}
+
+/// 满长度的段先按普通段解密，失败再按最后一段解密；不满的段只能是最后一段。
+/// 最后一段之后不允许再有数据，只有整个明文为空时最后一段才允许为空。
+pub(super) fn open_stream<C, R: Read, W: Write>(
+    key: &[u8; 32],
+    nonce: &[u8],
+    aad: &[u8],
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<u64>
+where
+    C: AeadInPlace + KeyInit,
+    C::NonceSize: Sub<U5>,
+    <C::NonceSize as Sub<U5>>::Output: ArrayLength<u8>,
+{
+    let failed = || {
+        anyhow::anyhow!(
+            "Decryption failed: wrong key or passphrase, wrong --aad, or truncated/corrupted data"
+        )
+    };
+    let cipher = C::new_from_slice(key).map_err(|e| anyhow::anyhow!(e))?;
+    let mut decryptor = DecryptorBE32::from_aead(cipher, GenericArray::from_slice(nonce));
+    let mut buf = vec![0u8; SEGMENT_SIZE + TAG_LEN];
+    let mut total = 0;
+    loop {
+        let len = read_segment(reader, &mut buf)?;
+        if len < TAG_LEN {
+            anyhow::bail!("Envelope is truncated");
+        }
+        let msg = &buf[..len];
+        if len == buf.len() {
+            if let Ok(segment) = decryptor.decrypt_next(Payload { msg, aad }) {
+                writer.write_all(&segment)?;
+                total += segment.len() as u64;
+                continue;
+            }
+        }
+        let segment = decryptor
+            .decrypt_last(Payload { msg, aad })
+            .map_err(|_| failed())?;
+        if segment.is_empty() && total > 0 {
+            anyhow::bail!("Final segment is empty");
+        }
+        writer.write_all(&segment)?;
+        if read_segment(reader, &mut buf[..1])? > 0 {
+            anyhow::bail!("Unexpected data after the final segment");
+        }
+        return Ok(total + segment.len() as u64);
+    }
+}
+
+impl ChaCha20 {
+    /// 以分段格式流式加密，返回明文字节数。
+    pub fn encrypt_stream<R: Read, W: Write>(
+        &self,
+        reader: &mut R,
+        writer: &mut W,
+        algorithm: EncryptionAlgorithm,
+        aad: &[u8],
+    ) -> Result<u64> {
+        let mut nonce = vec![0u8; StreamHeader::nonce_len(algorithm)];
+        OsRng.fill_bytes(&mut nonce);
+        let header = StreamHeader {
+            algorithm,
+            kdf: self.kdf.clone(),
+            nonce,
+        };
+        writer.write_all(&header.to_bytes())?;
+        let aad = header.aad(aad);
+        match algorithm {
+            EncryptionAlgorithm::ChaCha20Poly1305 => seal_stream::<ChaCha20Poly1305, _, _>(
+                &self.key,
+                &header.nonce,
+                &aad,
+                reader,
+                writer,
+            ),
+            EncryptionAlgorithm::XChaCha20Poly1305 => seal_stream::<XChaCha20Poly1305, _, _>(
+                &self.key,
+                &header.nonce,
+                &aad,
+                reader,
+                writer,
+            ),
+        }
+    }
+
+    /// 解密 `header` 之后的分段密文。每段认证通过后立即写出，
+    /// 因此出错时 `writer` 中可能已经有部分明文。
+    pub fn decrypt_stream<R: Read, W: Write>(
+        &self,
+        header: &StreamHeader,
+        reader: &mut R,
+        writer: &mut W,
+        aad: &[u8],
+    ) -> Result<u64> {
+        let aad = header.aad(aad);
+        match header.algorithm {
+            EncryptionAlgorithm::ChaCha20Poly1305 => open_stream::<ChaCha20Poly1305, _, _>(
+                &self.key,
+                &header.nonce,
+                &aad,
+                reader,
+                writer,
+            ),
+            EncryptionAlgorithm::XChaCha20Poly1305 => open_stream::<XChaCha20Poly1305, _, _>(
+                &self.key,
+                &header.nonce,
+                &aad,
+                reader,
+                writer,
+            ),
+        }
+    }
+}
+
+impl TextDecrypt for ChaCha20 {
+    fn decrypt(&self, envelope: &Envelope, aad: &[u8]) -> Result<Vec<u8>> {
+        match envelope.algorithm {
+            EncryptionAlgorithm::ChaCha20Poly1305 => {
+                open::<ChaCha20Poly1305>(&self.key, envelope, aad)
+            }
+            EncryptionAlgorithm::XChaCha20Poly1305 => {
+                open::<XChaCha20Poly1305>(&self.key, envelope, aad)
+            }
+        }
+    }
+}
+
+impl KeyLoader for ChaCha20 {
+    /// 旧版本的密钥文件在 32 字节密钥后附带了 12 字节 nonce，读取时忽略。
+    fn load_key(path: &str) -> Result<Self> {
+        let key = KeyFile::load(path)?.key_for(KeyKind::ChaCha20)?;
+        Ok(Self::new(*key))
+    }
+}
+
+impl KeyGenerator for ChaCha20 {
+    fn generate() -> Result<Vec<Vec<u8>>> {
+        let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        Ok(vec![key.to_vec()])
+    }
+}
+
+/// 按优先级读取口令：环境变量、文件（去掉末尾换行），否则在终端提示输入。
+/// `confirm` 为 true 时要求重复输入一次，用于加密。
+pub fn read_passphrase(
+    env: Option<&str>,
+    file: Option<&str>,
+    confirm: bool,
+) -> Result<Zeroizing<String>> {
+    read_passphrase_with(env, file, confirm, |name| std::env::var(name))
+}
+
+/// `var` 用于读取环境变量，测试中可以替换，避免修改进程的环境变量。
+fn read_passphrase_with(
+    env: Option<&str>,
+    file: Option<&str>,
+    confirm: bool,
+    var: impl Fn(&str) -> Result<String, std::env::VarError>,
+) -> Result<Zeroizing<String>> {
+    let passphrase = if let Some(env) = env {
+        Zeroizing::new(
+            var(env).with_context(|| format!("Environment variable {} is not set", env))?,
+        )
+    } else if let Some(file) = file {
+        let mut content = Zeroizing::new(fs::read_to_string(file)?);
+        let len = content.trim_end_matches(['\r', '\n']).len();
+        content.truncate(len);
+        content
+    } else {
+        let passphrase = Zeroizing::new(rpassword::prompt_password("Passphrase: ")?);
+        if confirm {
+            let again = Zeroizing::new(rpassword::prompt_password("Confirm passphrase: ")?);
+            if *passphrase != *again {
+                anyhow::bail!("Passphrases do not match");
+            }
+        }
+        passphrase
+    };
+    if passphrase.is_empty() {
+        anyhow::bail!("Passphrase must not be empty");
+    }
+    Ok(passphrase)
+}
+
+/// 以分段格式加密，内存占用与输入大小无关。
+pub fn process_encrypt(
+    input: &str,
+    output: Option<&str>,
+    key: EncryptionKey,
+    algorithm: EncryptionAlgorithm,
+    aad: &[u8],
+) -> Result<()> {
+    let mut reader = get_reader(input)?;
+    let chacha20 = match key {
+        EncryptionKey::File(path) => ChaCha20::load_key(&path)?,
+        EncryptionKey::Passphrase(passphrase) => {
+            ChaCha20::from_passphrase(&passphrase, Argon2Kdf::generate())?
+        }
+    };
+
+    let mut writer = get_writer(output)?;
+    chacha20.encrypt_stream(&mut reader, &mut writer, algorithm, aad)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// 同时支持分段格式与旧的整体加密格式。分段格式解密失败时会删除已写出的输出文件。
+pub fn process_decrypt(
+    input: &str,
+    output: Option<&str>,
+    key: EncryptionKey,
+    aad: &[u8],
+) -> Result<()> {
+    let mut reader = get_reader(input)?;
+    let mut prefix = [0u8; 6];
+    reader
+        .read_exact(&mut prefix)
+        .map_err(|_| anyhow::anyhow!("Input is not an rcli encrypted envelope"))?;
+    if &prefix[..MAGIC.len()] != MAGIC {
+        anyhow::bail!("Input is not an rcli encrypted envelope");
+    }
+
+    if prefix[4] == VERSION_STREAM {
+        let algorithm = EncryptionAlgorithm::try_from(prefix[5])?;
+        let header = StreamHeader::read_body(algorithm, &mut reader)?;
+        let chacha20 = cipher_for(key, header.kdf.as_ref())?;
+
+        let mut writer = get_writer(output)?;
+        let ret = chacha20
+            .decrypt_stream(&header, &mut reader, &mut writer, aad)
+            .and_then(|_| Ok(writer.flush()?));
+        if ret.is_err() {
+            drop(writer);
+            if let Some(path) = output.filter(|o| *o != "-") {
+                let _ = fs::remove_file(path);
+            }
+        }
+        return ret;
+    }
+
+    let mut buf = prefix.to_vec();
+    reader.read_to_end(&mut buf)?;
+    let envelope = Envelope::from_bytes(&buf)?;
+    let plaintext = cipher_for(key, envelope.kdf.as_ref())?.decrypt(&envelope, aad)?;
+
+    let mut writer = get_writer(output)?;
+    writer
+        .write_all(&plaintext)
+        .context("Failed to write plaintext")?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn cipher_for(key: EncryptionKey, kdf: Option<&Argon2Kdf>) -> Result<ChaCha20> {
+    match (key, kdf) {
+        (EncryptionKey::File(path), None) => ChaCha20::load_key(&path),
+        (EncryptionKey::Passphrase(passphrase), Some(kdf)) => {
+            ChaCha20::from_passphrase(&passphrase, kdf.clone())
+        }
+        (EncryptionKey::File(_), Some(_)) => {
+            anyhow::bail!(
+                "Input was encrypted with a passphrase, use --passphrase instead of --key"
+            )
+        }
+        (EncryptionKey::Passphrase(_), None) => {
+            anyhow::bail!("Input was encrypted with a key file, use --key instead of a passphrase")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALGORITHMS: [EncryptionAlgorithm; 2] = [
+        EncryptionAlgorithm::ChaCha20Poly1305,
+        EncryptionAlgorithm::XChaCha20Poly1305,
+    ];
+
+    impl Envelope {
+        fn to_bytes(&self) -> Vec<u8> {
+            let mut bytes = self.header();
+            bytes.extend_from_slice(&self.ciphertext);
+            bytes
+        }
+    }
+
+    fn seal<C: Aead + AeadCore + KeyInit>(
+        key: &[u8; 32],
+        algorithm: EncryptionAlgorithm,
+        kdf: Option<Argon2Kdf>,
+        msg: &[u8],
+        aad: &[u8],
+    ) -> Result<Envelope> {
+        let nonce = C::generate_nonce(&mut OsRng);
+        let mut envelope = Envelope {
+            algorithm,
+            kdf,
+            nonce: nonce.to_vec(),
+            ciphertext: Vec::new(),
+        };
+        let cipher = C::new_from_slice(key).map_err(|e| anyhow::anyhow!(e))?;
+        envelope.ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg,
+                    aad: &envelope.aad(aad),
+                },
+            )
+            .map_err(|e| anyhow::anyhow!(e))?;
+        Ok(envelope)
+    }
+
+    impl ChaCha20 {
+        /// 旧格式的整体加密，用于构造测试数据。
+        fn encrypt<R: Read>(
+            &self,
+            mut reader: R,
+            algorithm: EncryptionAlgorithm,
+            aad: &[u8],
+        ) -> Result<Envelope> {
+            let mut buf = Vec::new();
+            reader.read_to_end(&mut buf)?;
+            match algorithm {
+                EncryptionAlgorithm::ChaCha20Poly1305 => {
+                    seal::<ChaCha20Poly1305>(&self.key, algorithm, self.kdf.clone(), &buf, aad)
+                }
+                EncryptionAlgorithm::XChaCha20Poly1305 => {
+                    seal::<XChaCha20Poly1305>(&self.key, algorithm, self.kdf.clone(), &buf, aad)
+                }
+            }
+        }
+    }
+
+    fn test_cipher() -> ChaCha20 {
+        let key = ChaCha20::generate().unwrap();
+        ChaCha20::new(key[0][..].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_envelope_roundtrip() {
+        let cipher = test_cipher();
+        for algorithm in ALGORITHMS {
+            let envelope = cipher.encrypt(&b"hello rcli"[..], algorithm, b"").unwrap();
+            let bytes = envelope.to_bytes();
+            assert_eq!(&bytes[..4], MAGIC);
+            assert_eq!(bytes[4], VERSION);
+            assert_eq!(bytes[5], algorithm.id());
+            assert_eq!(envelope.nonce.len(), algorithm.nonce_len());
+
+            let parsed = Envelope::from_bytes(&bytes).unwrap();
+            assert_eq!(parsed, envelope);
+            assert_eq!(cipher.decrypt(&parsed, b"").unwrap(), b"hello rcli");
+        }
+    }
+
+    #[test]
+    fn test_fresh_nonce_per_message() {
+        let cipher = test_cipher();
+        for algorithm in ALGORITHMS {
+            let first = cipher.encrypt(&b"same"[..], algorithm, b"").unwrap();
+            let second = cipher.encrypt(&b"same"[..], algorithm, b"").unwrap();
+            assert_ne!(first.nonce, second.nonce);
+            assert_ne!(first.ciphertext, second.ciphertext);
+        }
+    }
+
+    #[test]
+    fn test_associated_data() {
+        let cipher = test_cipher();
+        for algorithm in ALGORITHMS {
+            let envelope = cipher
+                .encrypt(&b"payload"[..], algorithm, b"order-42")
+                .unwrap();
+            assert_eq!(cipher.decrypt(&envelope, b"order-42").unwrap(), b"payload");
+            assert!(cipher.decrypt(&envelope, b"order-43").is_err());
+            assert!(cipher.decrypt(&envelope, b"").is_err());
+        }
+    }
+
+    #[test]
+    fn test_tampered_envelope_is_rejected() {
+        let cipher = test_cipher();
+        let bytes = cipher
+            .encrypt(
+                &b"hello rcli"[..],
+                EncryptionAlgorithm::XChaCha20Poly1305,
+                b"",
+            )
+            .unwrap()
+            .to_bytes();
+
+        let mut tampered = bytes.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        let envelope = Envelope::from_bytes(&tampered).unwrap();
+        assert!(cipher.decrypt(&envelope, b"").is_err());
+
+        let mut tampered = bytes.clone();
+        tampered[6] ^= 1;
+        let envelope = Envelope::from_bytes(&tampered).unwrap();
+        assert!(cipher.decrypt(&envelope, b"").is_err());
+
+        let envelope = Envelope::from_bytes(&bytes).unwrap();
+        assert!(cipher.decrypt(&envelope, b"").is_ok());
+        assert!(test_cipher().decrypt(&envelope, b"").is_err());
+    }
+
+    #[test]
+    fn test_legacy_key_with_nonce() {
+        let dir = std::env::temp_dir().join(format!("rcli-legacy-key-{}", std::process::id()));
+        fs::write(&dir, [9u8; 44]).unwrap();
+        // 旧版本的密钥只有在 --legacy-key 打开时才会被使用
+        let err = ChaCha20::load_key(dir.to_str().unwrap()).err().unwrap();
+        assert!(err.to_string().contains("--legacy-key"), "{}", err);
+        let key = KeyFile::load(dir.to_str().unwrap())
+            .unwrap()
+            .key_for_with(KeyKind::ChaCha20, true)
+            .unwrap();
+        fs::remove_file(&dir).unwrap();
+        assert_eq!(*key, [9u8; 32]);
+    }
+
+    fn test_kdf() -> Argon2Kdf {
+        Argon2Kdf::with_params(64, 1, 1)
+    }
+
+    #[test]
+    fn test_passphrase_roundtrip() {
+        let cipher = ChaCha20::from_passphrase("correct horse", test_kdf()).unwrap();
+        let bytes = cipher
+            .encrypt(&b"secret"[..], EncryptionAlgorithm::XChaCha20Poly1305, b"")
+            .unwrap()
+            .to_bytes();
+        assert_eq!(bytes[4], VERSION_PASSPHRASE);
+        assert_eq!(bytes[6], KDF_ARGON2ID);
+
+        let envelope = Envelope::from_bytes(&bytes).unwrap();
+        let kdf = envelope.kdf.clone().unwrap();
+        assert_eq!((kdf.m_cost, kdf.t_cost, kdf.p_cost), (64, 1, 1));
+
+        let cipher = ChaCha20::from_passphrase("correct horse", kdf.clone()).unwrap();
+        assert_eq!(cipher.decrypt(&envelope, b"").unwrap(), b"secret");
+        let cipher = ChaCha20::from_passphrase("wrong horse", kdf).unwrap();
+        assert!(cipher.decrypt(&envelope, b"").is_err());
+    }
+
+    #[test]
+    fn test_kdf_header_is_authenticated() {
+        let cipher = ChaCha20::from_passphrase("correct horse", test_kdf()).unwrap();
+        let mut bytes = cipher
+            .encrypt(&b"secret"[..], EncryptionAlgorithm::ChaCha20Poly1305, b"")
+            .unwrap()
+            .to_bytes();
+        // 翻转盐的一位：派生出的密钥不同，且头部认证失败
+        bytes[20] ^= 1;
+        let envelope = Envelope::from_bytes(&bytes).unwrap();
+        let cipher =
+            ChaCha20::from_passphrase("correct horse", envelope.kdf.clone().unwrap()).unwrap();
+        assert!(cipher.decrypt(&envelope, b"").is_err());
+    }
+
+    #[test]
+    fn test_kdf_rejects_expensive_params() {
+        let kdf = Argon2Kdf::with_params(MAX_M_COST + 1, 1, 1);
+        assert!(kdf.derive(b"pass").is_err());
+        let kdf = Argon2Kdf::with_params(64, MAX_T_COST + 1, 1);
+        assert!(kdf.derive(b"pass").is_err());
+    }
+
+    #[test]
+    fn test_read_passphrase() {
+        let var = |name: &str| match name {
+            "RCLI_TEST_PASSPHRASE" => Ok("from env".to_string()),
+            _ => Err(std::env::VarError::NotPresent),
+        };
+        let passphrase =
+            read_passphrase_with(Some("RCLI_TEST_PASSPHRASE"), None, true, var).unwrap();
+        assert_eq!(passphrase.as_str(), "from env");
+        assert!(read_passphrase_with(Some("RCLI_TEST_PASSPHRASE_UNSET"), None, true, var).is_err());
+
+        let path =
+            std::env::temp_dir().join(format!("rcli-test-passphrase-{}.txt", std::process::id()));
+        fs::write(&path, "from file\r\n").unwrap();
+        let passphrase = read_passphrase(None, path.to_str(), true).unwrap();
+        assert_eq!(passphrase.as_str(), "from file");
+
+        fs::write(&path, "\n").unwrap();
+        assert!(read_passphrase(None, path.to_str(), true).is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    fn encrypt_to_vec(cipher: &ChaCha20, data: &[u8], algorithm: EncryptionAlgorithm) -> Vec<u8> {
+        let mut encrypted = Vec::new();
+        cipher
+            .encrypt_stream(&mut &data[..], &mut encrypted, algorithm, b"aad")
+            .unwrap();
+        encrypted
+    }
+
+    fn decrypt_from_slice(cipher: &ChaCha20, mut encrypted: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+        let mut prefix = [0u8; 6];
+        encrypted.read_exact(&mut prefix)?;
+        assert_eq!(prefix[4], VERSION_STREAM);
+        let algorithm = EncryptionAlgorithm::try_from(prefix[5])?;
+        let header = StreamHeader::read_body(algorithm, &mut encrypted)?;
+        let mut decrypted = Vec::new();
+        cipher.decrypt_stream(&header, &mut encrypted, &mut decrypted, aad)?;
+        Ok(decrypted)
+    }
+
+    #[test]
+    fn test_stream_roundtrip() {
+        let cipher = test_cipher();
+        for algorithm in ALGORITHMS {
+            let header_len = 7 + StreamHeader::nonce_len(algorithm);
+            for size in [
+                0,
+                1,
+                SEGMENT_SIZE - 1,
+                SEGMENT_SIZE,
+                SEGMENT_SIZE + 1,
+                3 * SEGMENT_SIZE + 7,
+            ] {
+                let data: Vec<u8> = (0..size).map(|i| (i % 251) as u8).collect();
+                let encrypted = encrypt_to_vec(&cipher, &data, algorithm);
+                let segments = size.div_ceil(SEGMENT_SIZE).max(1);
+                assert_eq!(encrypted.len(), header_len + size + segments * TAG_LEN);
+                assert_eq!(
+                    decrypt_from_slice(&cipher, &encrypted, b"aad").unwrap(),
+                    data,
+                    "{} bytes with {}",
+                    size,
+                    algorithm
+                );
+                assert!(decrypt_from_slice(&cipher, &encrypted, b"other").is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_stream_detects_truncation_and_reordering() {
+        let cipher = test_cipher();
+        let algorithm = EncryptionAlgorithm::XChaCha20Poly1305;
+        let data = vec![7u8; 3 * SEGMENT_SIZE];
+        let encrypted = encrypt_to_vec(&cipher, &data, algorithm);
+        let header_len = 7 + StreamHeader::nonce_len(algorithm);
+        let segment = SEGMENT_SIZE + TAG_LEN;
+
+        // 在分段边界截断：剩下的最后一段没有“最后一段”标志
+        let truncated = &encrypted[..header_len + 2 * segment];
+        assert!(decrypt_from_slice(&cipher, truncated, b"aad").is_err());
+        // 只剩头部
+        assert!(decrypt_from_slice(&cipher, &encrypted[..header_len], b"aad").is_err());
+        // 截掉最后一个字节
+        let truncated = &encrypted[..encrypted.len() - 1];
+        assert!(decrypt_from_slice(&cipher, truncated, b"aad").is_err());
+
+        // 交换前两段
+        let mut reordered = encrypted[..header_len].to_vec();
+        reordered.extend_from_slice(&encrypted[header_len + segment..header_len + 2 * segment]);
+        reordered.extend_from_slice(&encrypted[header_len..header_len + segment]);
+        reordered.extend_from_slice(&encrypted[header_len + 2 * segment..]);
+        assert!(decrypt_from_slice(&cipher, &reordered, b"aad").is_err());
+
+        assert_eq!(
+            decrypt_from_slice(&cipher, &encrypted, b"aad").unwrap(),
+            data
+        );
+    }
+
+    #[test]
+    fn test_stream_with_passphrase() {
+        let cipher = ChaCha20::from_passphrase("correct horse", test_kdf()).unwrap();
+        let encrypted = encrypt_to_vec(&cipher, b"secret", EncryptionAlgorithm::ChaCha20Poly1305);
+        assert_eq!(encrypted[6], KDF_ARGON2ID);
+
+        let mut rest = &encrypted[6..];
+        let kdf = Argon2Kdf::read_from(&mut rest).unwrap().unwrap();
+        let cipher = ChaCha20::from_passphrase("correct horse", kdf.clone()).unwrap();
+        assert_eq!(
+            decrypt_from_slice(&cipher, &encrypted, b"aad").unwrap(),
+            b"secret"
+        );
+        let cipher = ChaCha20::from_passphrase("wrong horse", kdf).unwrap();
+        assert!(decrypt_from_slice(&cipher, &encrypted, b"aad").is_err());
+    }
+
+    #[test]
+    fn test_process_decrypt_legacy_envelope() {
+        let dir = std::env::temp_dir().join(format!("rcli-legacy-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let key = ChaCha20::generate().unwrap();
+        let key_path = dir.join("base64.key");
+        let key_file = KeyFile::new(KeyKind::ChaCha20, &key[0]).unwrap();
+        fs::write(
+            &key_path,
+            key_file.encode(crate::cli::text::KeyEncoding::Raw).unwrap(),
+        )
+        .unwrap();
+        let envelope = ChaCha20::new(key[0][..].try_into().unwrap())
+            .encrypt(&b"legacy"[..], EncryptionAlgorithm::ChaCha20Poly1305, b"")
+            .unwrap();
+        let input = dir.join("legacy.enc");
+        fs::write(&input, envelope.to_bytes()).unwrap();
+        let output = dir.join("legacy.dec");
+
+        process_decrypt(
+            input.to_str().unwrap(),
+            output.to_str(),
+            EncryptionKey::File(key_path.to_str().unwrap().to_string()),
+            b"",
+        )
+        .unwrap();
+        assert_eq!(fs::read(&output).unwrap(), b"legacy");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_envelope() {
+        assert!(Envelope::from_bytes(b"hello").is_err());
+        assert!(Envelope::from_bytes(b"RCLI\x02\x01").is_err());
+        assert!(Envelope::from_bytes(b"RCLI\x01\x09").is_err());
+        assert!(Envelope::from_bytes(b"RCLI\x01\x01short").is_err());
+        assert!(Envelope::from_bytes(b"RCLI\x02\x01\x01short").is_err());
+        assert!(Envelope::from_bytes(b"RCLI\x03\x01\x00").is_err());
+        assert!(Envelope::from_bytes(b"RCLI\x04\x01").is_err());
+    }
+}
@@ -0,0 +1,214 @@
+use std::io::{self, Read};
+
+use anyhow::{Context, Result};
+use md5::Md5;
+use rayon::prelude::*;
+use sha1::Sha1;
+use sha2::{Digest, Sha224, Sha256, Sha384, Sha512};
+use sha3::{Sha3_224, Sha3_256, Sha3_384, Sha3_512};
+
+use crate::{cli::hash_opts::HashAlgorithm, get_reader};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// 并行计算每个输入的摘要，返回 `(十六进制摘要, 文件名)`，顺序与输入一致。
+pub fn process_hash(files: &[String], algorithm: HashAlgorithm) -> Result<Vec<(String, String)>> {
+    files
+        .par_iter()
+        .map(|file| {
+            let digest = hash_file(file, algorithm).with_context(|| file.clone())?;
+            Ok((digest, file.clone()))
+        })
+        .collect()
+}
+
+/// 校验 `sha256sum` 格式的清单文件，返回 `(文件名, 结果)`；无法读取的文件结果为 `None`。
+pub fn process_hash_check(
+    manifests: &[String],
+    algorithm: HashAlgorithm,
+) -> Result<Vec<(String, Option<bool>)>> {
+    let mut entries = Vec::new();
+    for manifest in manifests {
+        let mut content = String::new();
+        get_reader(manifest)?.read_to_string(&mut content)?;
+        for (i, line) in content.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let entry = parse_manifest_line(line).with_context(|| {
+                format!("{}:{}: improperly formatted checksum line", manifest, i + 1)
+            })?;
+            entries.push(entry);
+        }
+    }
+
+    Ok(entries
+        .par_iter()
+        .map(|(expected, file)| {
+            let result = hash_file(file, algorithm)
+                .ok()
+                .map(|digest| digest.eq_ignore_ascii_case(expected));
+            (file.clone(), result)
+        })
+        .collect())
+}
+
+/// 解析 `<hex>  <file>`（文本模式）或 `<hex> *<file>`（二进制模式）。
+pub(super) fn parse_manifest_line(line: &str) -> Option<(String, String)> {
+    let (digest, file) = line.split_once(' ')?;
+    let file = file.strip_prefix([' ', '*'])?;
+    if digest.is_empty() || file.is_empty() || !digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some((digest.to_string(), file.to_string()))
+}
+
+pub(super) fn hash_file(file: &str, algorithm: HashAlgorithm) -> Result<String> {
+    let reader = get_reader(file)?;
+    let digest = hash_reader(reader, algorithm)?;
+    Ok(hex::encode(digest))
+}
+
+pub fn hash_reader<R: Read>(reader: R, algorithm: HashAlgorithm) -> Result<Vec<u8>> {
+    let digest = match algorithm {
+        HashAlgorithm::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            read_chunks(reader, |chunk| {
+                hasher.update(chunk);
+            })?;
+            hasher.finalize().as_bytes().to_vec()
+        }
+        HashAlgorithm::Sha1 => digest_reader::<Sha1, _>(reader)?,
+        HashAlgorithm::Sha224 => digest_reader::<Sha224, _>(reader)?,
+        HashAlgorithm::Sha256 => digest_reader::<Sha256, _>(reader)?,
+        HashAlgorithm::Sha384 => digest_reader::<Sha384, _>(reader)?,
+        HashAlgorithm::Sha512 => digest_reader::<Sha512, _>(reader)?,
+        HashAlgorithm::Sha3_224 => digest_reader::<Sha3_224, _>(reader)?,
+        HashAlgorithm::Sha3_256 => digest_reader::<Sha3_256, _>(reader)?,
+        HashAlgorithm::Sha3_384 => digest_reader::<Sha3_384, _>(reader)?,
+        HashAlgorithm::Sha3_512 => digest_reader::<Sha3_512, _>(reader)?,
+        HashAlgorithm::Md5 => digest_reader::<Md5, _>(reader)?,
+        HashAlgorithm::Crc32 => {
+            let mut hasher = crc32fast::Hasher::new();
+            read_chunks(reader, |chunk| hasher.update(chunk))?;
+            hasher.finalize().to_be_bytes().to_vec()
+        }
+    };
+    Ok(digest)
+}
+
+fn digest_reader<D: Digest, R: Read>(reader: R) -> Result<Vec<u8>> {
+    let mut hasher = D::new();
+    read_chunks(reader, |chunk| hasher.update(chunk))?;
+    Ok(hasher.finalize().to_vec())
+}
+
+fn read_chunks<R: Read>(mut reader: R, mut f: impl FnMut(&[u8])) -> io::Result<()> {
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        f(&buf[..n]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_known_vectors() {
+        let cases = [
+            (
+                HashAlgorithm::Blake3,
+                "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85",
+            ),
+            (HashAlgorithm::Md5, "900150983cd24fb0d6963f7d28e17f72"),
+            (HashAlgorithm::Sha1, "a9993e364706816aba3e25717850c26c9cd0d89d"),
+            (
+                HashAlgorithm::Sha224,
+                "23097d223405d8228642a477bda255b32aadbce4bda0b3f7e36c9da7",
+            ),
+            (
+                HashAlgorithm::Sha256,
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                HashAlgorithm::Sha384,
+                "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7",
+            ),
+            (
+                HashAlgorithm::Sha512,
+                "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+            ),
+            (
+                HashAlgorithm::Sha3_224,
+                "e642824c3f8cf24ad09234ee7d3c766fc9a3a5168d0c94ad73b46fdf",
+            ),
+            (
+                HashAlgorithm::Sha3_256,
+                "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+            ),
+            (
+                HashAlgorithm::Sha3_384,
+                "ec01498288516fc926459f58e2c6ad8df9b473cb0fc08c2596da7cf0e49be4b298d88cea927ac7f539f1edf228376d25",
+            ),
+            (
+                HashAlgorithm::Sha3_512,
+                "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0",
+            ),
+            (HashAlgorithm::Crc32, "352441c2"),
+        ];
+        for (algorithm, expected) in cases {
+            let digest = hash_reader(&b"abc"[..], algorithm).unwrap();
+            assert_eq!(hex::encode(digest), expected, "{}", algorithm);
+        }
+    }
+
+    #[test]
+    fn test_process_hash_keeps_order() {
+        let files = vec![
+            "fixtures/b64/binary.bin".to_string(),
+            "Cargo.toml".to_string(),
+        ];
+        let ret = process_hash(&files, HashAlgorithm::Sha256).unwrap();
+        assert_eq!(ret.len(), 2);
+        assert_eq!(
+            ret[0].0,
+            "06dc6d6eee1a91a37a6f7e1236976c92e873bf54efdbe5fecc813b5cd02952cf"
+        );
+        assert_eq!(ret[0].1, "fixtures/b64/binary.bin");
+        assert_eq!(ret[1].1, "Cargo.toml");
+    }
+
+    #[test]
+    fn test_process_hash_check() {
+        let manifests = vec!["fixtures/hash/SHA256SUMS".to_string()];
+        let ret = process_hash_check(&manifests, HashAlgorithm::Sha256).unwrap();
+        assert_eq!(
+            ret,
+            vec![
+                ("fixtures/b64/binary.bin".to_string(), Some(true)),
+                ("fixtures/b64/binary_standard.b64".to_string(), Some(false)),
+                ("fixtures/hash/missing.bin".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_manifest_line() {
+        assert_eq!(
+            parse_manifest_line("abcd *dist/app.bin"),
+            Some(("abcd".to_string(), "dist/app.bin".to_string()))
+        );
+        assert_eq!(
+            parse_manifest_line("abcd  name with spaces"),
+            Some(("abcd".to_string(), "name with spaces".to_string()))
+        );
+        assert_eq!(parse_manifest_line("not a checksum line"), None);
+    }
+}
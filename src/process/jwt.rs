@@ -1,3 +1,5 @@
+use jsonwebtoken::errors::ErrorKind;
+
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 struct Claims {
     sub: String,
@@ -51,8 +53,19 @@ pub async fn process_verify_jwt_token(
         token,
         &jsonwebtoken::DecodingKey::from_secret(secret.as_bytes()),
         &validation,
-    )?;
-    println!("{:?}", token_data.claims);
+    )
+    .map_err(|e| match e.kind() {
+        ErrorKind::InvalidSignature
+        | ErrorKind::ExpiredSignature
+        | ErrorKind::ImmatureSignature
+        | ErrorKind::InvalidAudience
+        | ErrorKind::InvalidIssuer
+        | ErrorKind::InvalidSubject
+        | ErrorKind::MissingRequiredClaim(_) => {
+            crate::VerificationFailed::new(format!("Invalid token: {}", e)).into()
+        }
+        _ => anyhow::Error::from(e),
+    })?;
     Ok(token_data.claims.to_string())
 }
 
@@ -109,4 +122,16 @@ mod tests {
             .await
             .expect("Failed to write file");
     }
+
+    #[tokio::test]
+    async fn test_verify_with_wrong_secret() {
+        let aud = vec!["test".to_string()];
+        let token = process_create_jwt_token("test", aud.clone(), 3600, "secret")
+            .await
+            .unwrap();
+        let err = process_verify_jwt_token(&token, "other", aud)
+            .await
+            .unwrap_err();
+        assert_eq!(crate::exit_code(&err), crate::EXIT_VERIFY_FAILED);
+    }
 }
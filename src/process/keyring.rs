@@ -0,0 +1,550 @@
+use std::{
+    cell::Cell,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use ed25519_dalek::SigningKey;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use super::{
+    create_key,
+    key::{prompt_passphrase, KeyFile, KeyKind},
+};
+use crate::cli::{
+    text::{KeyEncoding, TextSignFormat},
+    write_private,
+};
+
+/// 设置后使用该目录作为密钥环，而不是数据目录下的 `rcli/keyring`。
+pub const KEYRING_DIR_ENV: &str = "RCLI_KEYRING_DIR";
+const META_FILE: &str = "meta.toml";
+const MAX_ID_LEN: usize = 64;
+const MINISIGN_UNSUPPORTED: &str =
+    "minisign keys are not stored in the keyring, pass the key file with --key";
+const PKCS8_UNSUPPORTED: &str =
+    "ECDSA and RSA keys are PKCS#8 / SPKI files and are not stored in the keyring, pass the key file with --key";
+
+/// 每个密钥一个子目录：`meta.toml` 记录算法，密钥文件按类型命名，
+/// 与 `text generate` 生成的文件相同。
+#[derive(Debug, Serialize, Deserialize)]
+struct KeyMeta {
+    algorithm: String,
+    encrypted: bool,
+}
+
+/// `rcli key list` 中的一项。
+#[derive(Debug)]
+pub struct KeyringEntry {
+    pub id: String,
+    pub format: TextSignFormat,
+    pub kinds: Vec<KeyKind>,
+    pub encrypted: bool,
+}
+
+pub struct Keyring {
+    root: PathBuf,
+}
+
+impl Keyring {
+    /// 打开 `RCLI_KEYRING_DIR` 指定的目录，未设置时使用 `dirs::data_dir()/rcli/keyring`。
+    pub fn open() -> Result<Self> {
+        let root = match std::env::var_os(KEYRING_DIR_ENV) {
+            Some(dir) => PathBuf::from(dir),
+            None => dirs::data_dir()
+                .context("Cannot find the user data directory, set RCLI_KEYRING_DIR")?
+                .join("rcli")
+                .join("keyring"),
+        };
+        Ok(Self::at(root))
+    }
+
+    pub fn at(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// 无法读取的密钥目录（例如被手工修改过的 `meta.toml`）会在标准错误输出警告后跳过，
+    /// 不影响其他密钥。
+    pub fn list(&self) -> Result<Vec<KeyringEntry>> {
+        if !self.root.exists() {
+            return Ok(Vec::new());
+        }
+        let mut entries = Vec::new();
+        for dir in fs::read_dir(&self.root)? {
+            let dir = dir?;
+            let id = dir.file_name().to_string_lossy().into_owned();
+            // 跳过写入中途失败留下的临时目录
+            if dir.file_type()?.is_dir() && check_id(&id).is_ok() {
+                match self.get(&id) {
+                    Ok(entry) => entries.push(entry),
+                    Err(e) => eprintln!("WARNING: skipping key '{}': {:#}", id, e),
+                }
+            }
+        }
+        entries.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(entries)
+    }
+
+    pub fn get(&self, id: &str) -> Result<KeyringEntry> {
+        check_id(id)?;
+        let dir = self.root.join(id);
+        let meta = fs::read_to_string(dir.join(META_FILE)).with_context(|| {
+            format!("Key '{}' not found in keyring {}", id, self.root.display())
+        })?;
+        let meta: KeyMeta =
+            toml::from_str(&meta).with_context(|| format!("Invalid metadata for key '{}'", id))?;
+        let format: TextSignFormat = meta.algorithm.parse()?;
+        let kinds = kinds_of(format)
+            .into_iter()
+            .filter(|kind| dir.join(file_name(*kind)).exists())
+            .collect();
+        Ok(KeyringEntry {
+            id: id.to_string(),
+            format,
+            kinds,
+            encrypted: meta.encrypted,
+        })
+    }
+
+    /// 导入已有的密钥文件，文件按原样保存（加密的私钥保持加密）。
+    /// 只导入 ed25519 私钥时会同时保存对应的公钥，便于 `verify --key-id`。
+    pub fn import(&self, id: &str, paths: &[String]) -> Result<KeyringEntry> {
+        let mut files: Vec<(KeyKind, Zeroizing<Vec<u8>>)> = Vec::new();
+        let mut encrypted = false;
+        let mut derived_public = None;
+        for path in paths {
+            let data = Zeroizing::new(fs::read(path)?);
+            let prompted = Cell::new(false);
+            let key = KeyFile::decode_with(&data, &|| {
+                prompted.set(true);
+                prompt_passphrase()
+            })
+            .with_context(|| format!("Failed to load key file {}", path))?;
+            let kind = key.kind().with_context(|| {
+                format!(
+                    "{} is an untyped legacy key, regenerate it with `rcli text generate` or `rcli key add --generate`",
+                    path
+                )
+            })?;
+            if files.iter().any(|(k, _)| *k == kind) {
+                anyhow::bail!("More than one {} given", kind);
+            }
+            if kind == KeyKind::Ed25519Private {
+                let secret = key.key_for(kind)?;
+                derived_public = Some(SigningKey::from_bytes(&secret).verifying_key().to_bytes());
+            }
+            encrypted |= prompted.get();
+            files.push((kind, data));
+        }
+        let format = match files.first() {
+            Some((kind, _)) => format_of(*kind),
+            None => anyhow::bail!("No key files given"),
+        };
+        if files.iter().any(|(kind, _)| format_of(*kind) != format) {
+            anyhow::bail!("Key files of different algorithms cannot share one key id");
+        }
+        if let Some(public) = derived_public {
+            match files
+                .iter()
+                .find(|(kind, _)| *kind == KeyKind::Ed25519Public)
+            {
+                Some((_, data)) => {
+                    let key = KeyFile::decode(data)?.key_for(KeyKind::Ed25519Public)?;
+                    if key[..] != public {
+                        anyhow::bail!("The ed25519 public key does not match the private key");
+                    }
+                }
+                None => files.push((
+                    KeyKind::Ed25519Public,
+                    Zeroizing::new(
+                        KeyFile::new(KeyKind::Ed25519Public, &public)?.encode(KeyEncoding::Raw)?,
+                    ),
+                )),
+            }
+        }
+        self.store(id, format, encrypted, &files)
+    }
+
+    /// 生成新密钥并保存，指定口令时私钥与对称密钥用口令加密。
+    pub fn generate(
+        &self,
+        id: &str,
+        format: TextSignFormat,
+        passphrase: Option<&str>,
+    ) -> Result<KeyringEntry> {
+        if format == TextSignFormat::Minisign {
+            anyhow::bail!(MINISIGN_UNSUPPORTED);
+        }
+        if format.is_pkcs8() {
+            anyhow::bail!(PKCS8_UNSUPPORTED);
+        }
+        let keys = create_key(format)?;
+        let mut files = Vec::new();
+        for (kind, key) in kinds_of(format).into_iter().zip(keys.iter()) {
+            let key = KeyFile::new(kind, key)?;
+            let data = match passphrase {
+                Some(passphrase) if !kind.is_public() => {
+                    key.encode_encrypted(KeyEncoding::Raw, passphrase)?
+                }
+                _ => key.encode(KeyEncoding::Raw)?,
+            };
+            files.push((kind, Zeroizing::new(data)));
+        }
+        self.store(id, format, passphrase.is_some(), &files)
+    }
+
+    /// 先写入临时目录再重命名，避免留下不完整的密钥。
+    fn store(
+        &self,
+        id: &str,
+        format: TextSignFormat,
+        encrypted: bool,
+        files: &[(KeyKind, Zeroizing<Vec<u8>>)],
+    ) -> Result<KeyringEntry> {
+        check_id(id)?;
+        let dir = self.root.join(id);
+        if dir.exists() {
+            anyhow::bail!("Key '{}' already exists in the keyring", id);
+        }
+        create_private_dir(&self.root)?;
+        let tmp = self.root.join(format!(".{}.tmp", id));
+        if tmp.exists() {
+            fs::remove_dir_all(&tmp)?;
+        }
+        create_private_dir(&tmp)?;
+        let meta = KeyMeta {
+            algorithm: format.to_string(),
+            encrypted,
+        };
+        let written = (|| -> Result<()> {
+            fs::write(tmp.join(META_FILE), toml::to_string(&meta)?)?;
+            for (kind, data) in files {
+                write_private(&path_str(&tmp.join(file_name(*kind)))?, data, false)?;
+            }
+            fs::rename(&tmp, &dir)?;
+            Ok(())
+        })();
+        if written.is_err() {
+            let _ = fs::remove_dir_all(&tmp);
+        }
+        written?;
+        self.get(id)
+    }
+
+    pub fn remove(&self, id: &str) -> Result<()> {
+        self.get(id)?;
+        fs::remove_dir_all(self.root.join(id))?;
+        Ok(())
+    }
+
+    pub fn rename(&self, from: &str, to: &str) -> Result<()> {
+        self.get(from)?;
+        check_id(to)?;
+        if self.root.join(to).exists() {
+            anyhow::bail!("Key '{}' already exists in the keyring", to);
+        }
+        fs::rename(self.root.join(from), self.root.join(to))?;
+        Ok(())
+    }
+
+    /// 签名（`signing` 为 true）或验证时使用的密钥文件与算法，
+    /// `format` 为用户显式指定的算法，与密钥环中记录的不一致时报错。
+    pub fn resolve(
+        &self,
+        id: &str,
+        signing: bool,
+        format: Option<TextSignFormat>,
+    ) -> Result<(String, TextSignFormat)> {
+        let entry = self.get(id)?;
+        if let Some(format) = format {
+            // HMAC 密钥与哈希算法无关，可以按 hmac-sha256/384/512 中的任意一种使用
+            if format.is_hmac() && entry.format.is_hmac() {
+                let (path, _) = self.resolve(id, signing, None)?;
+                return Ok((path, format));
+            }
+            if format != entry.format {
+                anyhow::bail!(
+                    "--format {} does not match key '{}' (algorithm: {})",
+                    format,
+                    id,
+                    entry.format
+                );
+            }
+        }
+        let kind = match entry.format {
+            TextSignFormat::Blake3 => KeyKind::Blake3,
+            TextSignFormat::Ed25519 if signing => KeyKind::Ed25519Private,
+            TextSignFormat::Ed25519 => KeyKind::Ed25519Public,
+            TextSignFormat::ChaCha20 => {
+                anyhow::bail!("Key '{}' is a chacha20 encryption key and cannot sign", id)
+            }
+            TextSignFormat::HmacSha256
+            | TextSignFormat::HmacSha384
+            | TextSignFormat::HmacSha512 => KeyKind::Hmac,
+            TextSignFormat::X25519 => {
+                anyhow::bail!(
+                    "Key '{}' is an x25519 key agreement key and cannot sign",
+                    id
+                )
+            }
+            TextSignFormat::Minisign => anyhow::bail!(MINISIGN_UNSUPPORTED),
+            TextSignFormat::EcdsaP256 | TextSignFormat::EcdsaSecp256k1 | TextSignFormat::RsaPss => {
+                anyhow::bail!(PKCS8_UNSUPPORTED)
+            }
+        };
+        if !entry.kinds.contains(&kind) {
+            anyhow::bail!("Key '{}' has no {}", id, kind);
+        }
+        let path = path_str(&self.root.join(id).join(file_name(kind)))?;
+        Ok((path, entry.format))
+    }
+
+    /// 导出公钥，默认编码为 `pem`。
+    pub fn export_public(&self, id: &str, encoding: KeyEncoding) -> Result<Vec<u8>> {
+        let entry = self.get(id)?;
+        let kind = entry
+            .kinds
+            .iter()
+            .copied()
+            .find(KeyKind::is_public)
+            .with_context(|| {
+                format!(
+                    "Key '{}' has no public key, use --private to export the secret key",
+                    id
+                )
+            })?;
+        let path = self.root.join(id).join(file_name(kind));
+        KeyFile::decode(&fs::read(path)?)?.encode(encoding)
+    }
+
+    /// 导出私钥或对称密钥：不指定编码时按原样导出（加密的密钥保持加密），
+    /// 指定编码时解密后重新编码。
+    pub fn export_private(
+        &self,
+        id: &str,
+        encoding: Option<KeyEncoding>,
+    ) -> Result<Zeroizing<Vec<u8>>> {
+        let entry = self.get(id)?;
+        let kind = entry
+            .kinds
+            .iter()
+            .copied()
+            .find(|kind| !kind.is_public())
+            .with_context(|| format!("Key '{}' has no private key", id))?;
+        let data = Zeroizing::new(fs::read(self.root.join(id).join(file_name(kind)))?);
+        match encoding {
+            Some(encoding) => Ok(Zeroizing::new(KeyFile::decode(&data)?.encode(encoding)?)),
+            None => Ok(data),
+        }
+    }
+}
+
+/// 只允许字母、数字、`.`、`_` 与 `-`，不能以 `.` 开头，避免路径穿越与隐藏目录。
+fn check_id(id: &str) -> Result<()> {
+    let valid = !id.is_empty()
+        && id.len() <= MAX_ID_LEN
+        && !id.starts_with('.')
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if !valid {
+        anyhow::bail!(
+            "Invalid key id '{}': use up to {} letters, digits, '.', '_' or '-', not starting with '.'",
+            id,
+            MAX_ID_LEN
+        );
+    }
+    Ok(())
+}
+
+fn create_private_dir(path: &Path) -> Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(path)?;
+    Ok(())
+}
+
+fn path_str(path: &Path) -> Result<String> {
+    path.to_str()
+        .map(str::to_string)
+        .with_context(|| format!("Non UTF-8 keyring path: {}", path.display()))
+}
+
+fn file_name(kind: KeyKind) -> &'static str {
+    match kind {
+        KeyKind::Blake3 => "blake3.key",
+        KeyKind::Ed25519Private => "ed25519.priv",
+        KeyKind::Ed25519Public => "ed25519.pub",
+        KeyKind::ChaCha20 => "base64.key",
+        KeyKind::Hmac => "hmac.key",
+        KeyKind::X25519Private => "x25519.priv",
+        KeyKind::X25519Public => "x25519.pub",
+    }
+}
+
+/// 与 `create_key` 的返回顺序一致。
+fn kinds_of(format: TextSignFormat) -> Vec<KeyKind> {
+    match format {
+        TextSignFormat::Blake3 => vec![KeyKind::Blake3],
+        TextSignFormat::Ed25519 => vec![KeyKind::Ed25519Private, KeyKind::Ed25519Public],
+        TextSignFormat::ChaCha20 => vec![KeyKind::ChaCha20],
+        TextSignFormat::HmacSha256 | TextSignFormat::HmacSha384 | TextSignFormat::HmacSha512 => {
+            vec![KeyKind::Hmac]
+        }
+        TextSignFormat::X25519 => vec![KeyKind::X25519Private, KeyKind::X25519Public],
+        TextSignFormat::Minisign
+        | TextSignFormat::EcdsaP256
+        | TextSignFormat::EcdsaSecp256k1
+        | TextSignFormat::RsaPss => vec![],
+    }
+}
+
+fn format_of(kind: KeyKind) -> TextSignFormat {
+    match kind {
+        KeyKind::Blake3 => TextSignFormat::Blake3,
+        KeyKind::Ed25519Private | KeyKind::Ed25519Public => TextSignFormat::Ed25519,
+        KeyKind::ChaCha20 => TextSignFormat::ChaCha20,
+        // 导入的 HMAC 密钥默认记录为 hmac-sha256，使用时可以用 --format 选择其他哈希算法
+        KeyKind::Hmac => TextSignFormat::HmacSha256,
+        KeyKind::X25519Private | KeyKind::X25519Public => TextSignFormat::X25519,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cli::text::{EcdsaEncoding, SignatureEncoding},
+        process_sign, process_verify,
+    };
+
+    fn temp_keyring(name: &str) -> Keyring {
+        let dir =
+            std::env::temp_dir().join(format!("rcli-keyring-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Keyring::at(dir)
+    }
+
+    #[test]
+    fn test_keyring_generate_and_sign() -> Result<()> {
+        let keyring = temp_keyring("sign");
+        let entry = keyring.generate("release-2026", TextSignFormat::Ed25519, None)?;
+        assert_eq!(entry.format, TextSignFormat::Ed25519);
+        assert_eq!(
+            entry.kinds,
+            [KeyKind::Ed25519Private, KeyKind::Ed25519Public]
+        );
+
+        let (private, format) = keyring.resolve("release-2026", true, None)?;
+        let (public, _) = keyring.resolve("release-2026", false, Some(TextSignFormat::Ed25519))?;
+        assert!(keyring
+            .resolve("release-2026", true, Some(TextSignFormat::Blake3))
+            .is_err());
+        let input = "Cargo.toml";
+        let signature = process_sign(
+            input,
+            &private,
+            format,
+            SignatureEncoding::Base64,
+            EcdsaEncoding::Raw,
+        )?;
+        assert!(process_verify(
+            input,
+            &public,
+            &signature,
+            format,
+            SignatureEncoding::Base64
+        )?);
+
+        keyring.generate("mac", TextSignFormat::Blake3, None)?;
+        let ids: Vec<_> = keyring.list()?.into_iter().map(|e| e.id).collect();
+        assert_eq!(ids, ["mac", "release-2026"]);
+
+        // 损坏的密钥目录不影响列出其他密钥
+        fs::create_dir(keyring.root().join("broken"))?;
+        fs::write(keyring.root().join("mac").join(META_FILE), "algorithm = 1")?;
+        let ids: Vec<_> = keyring.list()?.into_iter().map(|e| e.id).collect();
+        assert_eq!(ids, ["release-2026"]);
+        fs::remove_dir_all(keyring.root())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_keyring_import_derives_public_key() -> Result<()> {
+        let keyring = temp_keyring("import");
+        let private = keyring.root().with_extension("priv");
+        fs::write(
+            &private,
+            include_bytes!("../../fixtures/keys/ed25519_pkcs8.pem"),
+        )?;
+        let entry = keyring.import("ci", &[path_str(&private)?])?;
+        assert_eq!(
+            entry.kinds,
+            [KeyKind::Ed25519Private, KeyKind::Ed25519Public]
+        );
+        assert!(!entry.encrypted);
+
+        // 导出的公钥与 openssl 生成的 SPKI 一致
+        let exported = keyring.export_public("ci", KeyEncoding::Pem)?;
+        assert_eq!(
+            exported,
+            include_bytes!("../../fixtures/keys/ed25519_spki.pem")
+        );
+        assert_eq!(
+            &keyring.export_private("ci", None)?[..],
+            include_bytes!("../../fixtures/keys/ed25519_pkcs8.pem")
+        );
+
+        // 不匹配的公钥会被拒绝
+        let public = keyring.root().with_extension("pub");
+        fs::write(
+            &public,
+            KeyFile::new(KeyKind::Ed25519Public, &[7u8; 32])?.encode(KeyEncoding::Raw)?,
+        )?;
+        assert!(keyring
+            .import("mismatch", &[path_str(&private)?, path_str(&public)?])
+            .is_err());
+        fs::remove_file(private)?;
+        fs::remove_file(public)?;
+        fs::remove_dir_all(keyring.root())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_keyring_rename_and_remove() -> Result<()> {
+        let keyring = temp_keyring("rename");
+        keyring.generate("old", TextSignFormat::Blake3, None)?;
+        keyring.generate("other", TextSignFormat::ChaCha20, None)?;
+        // 与 `text generate --format base64` 的文件名一致
+        assert!(keyring.root().join("other").join("base64.key").exists());
+        assert!(keyring
+            .generate("old", TextSignFormat::Blake3, None)
+            .is_err());
+        assert!(keyring.rename("old", "other").is_err());
+        keyring.rename("old", "new")?;
+        assert!(keyring.get("old").is_err());
+        assert_eq!(keyring.get("new")?.format, TextSignFormat::Blake3);
+        assert!(keyring.resolve("other", true, None).is_err());
+        assert!(keyring.export_public("new", KeyEncoding::Pem).is_err());
+        keyring.remove("new")?;
+        assert!(keyring.remove("new").is_err());
+        fs::remove_dir_all(keyring.root())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_keyring_rejects_invalid_ids() {
+        for id in ["", ".hidden", "../escape", "a/b", "with space"] {
+            assert!(check_id(id).is_err(), "{}", id);
+        }
+        assert!(check_id("release-2026.v1_a").is_ok());
+    }
+}
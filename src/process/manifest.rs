@@ -0,0 +1,236 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+
+use super::{
+    hash::{hash_file, parse_manifest_line},
+    text::{sign_detached, verify_detached, SignatureFile},
+};
+use crate::{
+    cli::{hash_opts::HashAlgorithm, text::TextSignFormat},
+    VerificationFailed,
+};
+
+/// 清单的默认文件名，内容与 `b3sum` 的输出兼容，可以在目录中用 `b3sum --check B3SUMS` 校验。
+pub const DIR_MANIFEST_NAME: &str = "B3SUMS";
+
+/// `verify-dir` 的结果，路径均为相对于目录的 `/` 分隔路径。
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DirVerifyReport {
+    pub checked: usize,
+    pub added: Vec<String>,
+    pub missing: Vec<String>,
+    pub modified: Vec<String>,
+}
+
+impl DirVerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.added.is_empty() && self.missing.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// 清单的签名文件与清单放在一起，文件名为清单加 `.sig`。
+pub fn manifest_signature_path(manifest: &str) -> String {
+    format!("{}.sig", manifest)
+}
+
+/// 用 blake3 计算目录下所有文件的摘要并写入清单，再用 Ed25519 签名清单。
+/// 清单与签名文件位于目录中时不会被收录，返回收录的文件数。
+pub fn process_sign_dir(dir: &str, manifest: &str, key: &str) -> Result<usize> {
+    let files = hash_dir(dir, manifest)?;
+    let content: String = files
+        .iter()
+        .map(|(path, digest)| format!("{}  {}\n", digest, path))
+        .collect();
+    let signature = sign_detached(content.as_bytes(), key, TextSignFormat::Ed25519)?;
+    fs::write(manifest, &content)?;
+    fs::write(manifest_signature_path(manifest), signature.to_string())?;
+    Ok(files.len())
+}
+
+/// 先验证清单的签名，再重新计算目录中文件的摘要并与清单比较。
+/// 签名无效时返回错误，不会信任清单中的任何内容。
+pub fn process_verify_dir(dir: &str, manifest: &str, key: &str) -> Result<DirVerifyReport> {
+    let content =
+        fs::read(manifest).with_context(|| format!("Failed to read manifest {}", manifest))?;
+    let signature = SignatureFile::load(&manifest_signature_path(manifest))?;
+    if signature.format()? != TextSignFormat::Ed25519 {
+        anyhow::bail!(
+            "Directory manifests are signed with ed25519, found {}",
+            signature.format()?
+        );
+    }
+    if !verify_detached(&content[..], key, &signature)? {
+        return Err(VerificationFailed::new(format!(
+            "The manifest signature is invalid, {} may have been tampered with",
+            manifest
+        ))
+        .into());
+    }
+
+    let mut expected = BTreeMap::new();
+    for (i, line) in std::str::from_utf8(&content)?.lines().enumerate() {
+        let (digest, path) = parse_manifest_line(line).with_context(|| {
+            format!("{}:{}: improperly formatted checksum line", manifest, i + 1)
+        })?;
+        check_relative(&path)
+            .with_context(|| format!("{}:{}: invalid path {}", manifest, i + 1, path))?;
+        expected.insert(path, digest);
+    }
+
+    let actual = hash_dir(dir, manifest)?;
+    let mut report = DirVerifyReport {
+        checked: actual.len(),
+        ..Default::default()
+    };
+    for (path, digest) in &actual {
+        match expected.remove(path) {
+            None => report.added.push(path.clone()),
+            Some(recorded) if !recorded.eq_ignore_ascii_case(digest) => {
+                report.modified.push(path.clone())
+            }
+            Some(_) => {}
+        }
+    }
+    report.missing = expected.into_keys().collect();
+    Ok(report)
+}
+
+/// 并行计算目录下所有普通文件的 blake3 摘要，按相对路径排序；跳过清单与其签名文件。
+fn hash_dir(dir: &str, manifest: &str) -> Result<BTreeMap<String, String>> {
+    let root = fs::canonicalize(dir).with_context(|| format!("Directory not found: {}", dir))?;
+    let excluded = [manifest.to_string(), manifest_signature_path(manifest)]
+        .iter()
+        .filter_map(|path| absolute(Path::new(path)))
+        .collect::<Vec<_>>();
+    let mut files = Vec::new();
+    walk(&root, &root, &mut files)?;
+    files.retain(|(path, _)| !excluded.contains(path));
+    files
+        .par_iter()
+        .map(|(path, name)| {
+            let path = path.to_str().with_context(|| name.clone())?;
+            let digest = hash_file(path, HashAlgorithm::Blake3).with_context(|| name.clone())?;
+            Ok((name.clone(), digest))
+        })
+        .collect()
+}
+
+/// 收集 `dir` 下的普通文件及其相对于 `root` 的 `/` 分隔路径，不跟随符号链接。
+fn walk(root: &Path, dir: &Path, files: &mut Vec<(PathBuf, String)>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            walk(root, &path, files)?;
+        } else if file_type.is_file() {
+            let name = path
+                .strip_prefix(root)?
+                .iter()
+                .map(|part| part.to_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+                .with_context(|| format!("Non UTF-8 file name: {}", path.display()))?
+                .join("/");
+            if name.contains('\n') {
+                anyhow::bail!(
+                    "File names with newlines are not supported: {}",
+                    path.display()
+                );
+            }
+            files.push((path, name));
+        } else {
+            anyhow::bail!(
+                "Symbolic links and special files are not supported: {}",
+                path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// 清单文件可能尚不存在，只规范化其所在目录。
+fn absolute(path: &Path) -> Option<PathBuf> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    Some(fs::canonicalize(parent).ok()?.join(path.file_name()?))
+}
+
+/// 清单中的路径必须是目录内的相对路径。
+fn check_relative(path: &str) -> Result<()> {
+    if path.starts_with('/')
+        || path
+            .split('/')
+            .any(|part| part.is_empty() || part == "." || part == "..")
+    {
+        anyhow::bail!("Manifest paths must be relative and stay inside the directory");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rcli-dir-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("app.tar.gz"), b"release artifact").unwrap();
+        fs::write(dir.join("nested/README"), b"docs").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_sign_and_verify_dir() -> Result<()> {
+        let dir = temp_dir("sign");
+        let dir_str = dir.to_str().unwrap();
+        let manifest = dir.join(DIR_MANIFEST_NAME);
+        let manifest = manifest.to_str().unwrap();
+        let private = "fixtures/keys/id_ed25519";
+        let public = "fixtures/keys/id_ed25519.pub";
+
+        assert_eq!(process_sign_dir(dir_str, manifest, private)?, 2);
+        let content = fs::read_to_string(manifest)?;
+        assert!(content.ends_with("  nested/README\n"), "{}", content);
+        let report = process_verify_dir(dir_str, manifest, public)?;
+        assert!(report.is_ok());
+        assert_eq!(report.checked, 2);
+
+        fs::write(dir.join("app.tar.gz"), b"tampered artifact")?;
+        fs::remove_file(dir.join("nested/README"))?;
+        fs::write(dir.join("extra"), b"new")?;
+        let report = process_verify_dir(dir_str, manifest, public)?;
+        assert_eq!(
+            report,
+            DirVerifyReport {
+                checked: 2,
+                added: vec!["extra".to_string()],
+                missing: vec!["nested/README".to_string()],
+                modified: vec!["app.tar.gz".to_string()],
+            }
+        );
+
+        // 修改清单后签名失效
+        fs::write(manifest, content.replace("nested/README", "nested/OTHER"))?;
+        let err = process_verify_dir(dir_str, manifest, public).unwrap_err();
+        assert_eq!(crate::exit_code(&err), crate::EXIT_VERIFY_FAILED);
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_relative() {
+        for path in ["/etc/passwd", "../up", "a/../../b", "a//b", "./a"] {
+            assert!(check_relative(path).is_err(), "{}", path);
+        }
+        assert!(check_relative("nested/README").is_ok());
+    }
+}
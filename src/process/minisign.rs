@@ -0,0 +1,513 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use blake2::{digest::consts::U32, Blake2b, Blake2b512, Digest};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use rand::{rngs::OsRng, RngCore};
+use zeroize::Zeroizing;
+
+use super::key::{prompt_passphrase, PassphraseFn};
+use crate::get_reader;
+
+/// minisign（https://jedisct1.github.io/minisign/）的密钥与签名格式。
+/// * 公钥：`Ed` | 8 字节 key id | 32 字节公钥；
+/// * 私钥：`Ed` | KDF（`Sc` 或全零表示未加密）| `B2` | 盐 | opslimit | memlimit |
+///   key id | 64 字节私钥 | BLAKE2b-256 校验和，后三项用 scrypt 的输出异或加密；
+/// * 签名：`ED`（对 BLAKE2b-512 预哈希签名）或旧版的 `Ed`（对原文签名）| key id | 签名，
+///   另有一个对“签名 + 可信注释”的全局签名。
+const SIG_ALG: &[u8; 2] = b"Ed";
+const SIG_ALG_PREHASHED: &[u8; 2] = b"ED";
+const KDF_ALG: &[u8; 2] = b"Sc";
+const KDF_NONE: &[u8; 2] = &[0, 0];
+const CHK_ALG: &[u8; 2] = b"B2";
+const KEYNUM_LEN: usize = 8;
+const SALT_LEN: usize = 32;
+const CHECKSUM_LEN: usize = 32;
+const PUBLIC_KEY_LEN: usize = 2 + KEYNUM_LEN + 32;
+/// key id、64 字节私钥（种子加公钥）与校验和，即需要加密的部分。
+const KEYNUM_SK_LEN: usize = KEYNUM_LEN + 64 + CHECKSUM_LEN;
+const SECRET_KEY_LEN: usize = 6 + SALT_LEN + 16 + KEYNUM_SK_LEN;
+const SIGNATURE_LEN: usize = 2 + KEYNUM_LEN + 64;
+/// minisign 默认的 scrypt 代价（libsodium 的 opslimit / memlimit，对应 N = 2^20, r = 8, p = 1）。
+const DEFAULT_OPSLIMIT: u64 = 33_554_432;
+const DEFAULT_MEMLIMIT: u64 = 1_073_741_824;
+/// 拒绝超出 minisign 默认代价的私钥，防止恶意文件消耗过多内存。
+const MAX_SCRYPT_LOG_N: u8 = 20;
+
+const UNTRUSTED_PREFIX: &str = "untrusted comment: ";
+const TRUSTED_PREFIX: &str = "trusted comment: ";
+
+/// 判断内容是否为 minisign 格式（以 `untrusted comment:` 开头）。
+pub fn is_minisign(data: &[u8]) -> bool {
+    data.starts_with(UNTRUSTED_PREFIX.as_bytes())
+}
+
+struct PublicKey {
+    keynum: [u8; KEYNUM_LEN],
+    key: VerifyingKey,
+}
+
+struct SecretKey {
+    keynum: [u8; KEYNUM_LEN],
+    key: SigningKey,
+}
+
+struct MinisignSignature {
+    prehashed: bool,
+    keynum: [u8; KEYNUM_LEN],
+    signature: Signature,
+    trusted_comment: String,
+    global_signature: Signature,
+}
+
+fn key_id(keynum: &[u8; KEYNUM_LEN]) -> String {
+    format!("{:016X}", u64::from_le_bytes(*keynum))
+}
+
+/// 读取注释行之后的 base64 行；公钥也可以只有 base64 一行（`minisign -P` 的参数）。
+fn decode_base64_lines(text: &str) -> Result<Vec<Vec<u8>>> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(UNTRUSTED_PREFIX.trim_end()))
+        .map(|line| {
+            STANDARD
+                .decode(line)
+                .context("Invalid base64 in minisign file")
+        })
+        .collect()
+}
+
+impl PublicKey {
+    fn decode(text: &str) -> Result<Self> {
+        let lines = decode_base64_lines(text)?;
+        let [data] = lines.as_slice() else {
+            anyhow::bail!("Invalid minisign public key");
+        };
+        if data.len() != PUBLIC_KEY_LEN || &data[..2] != SIG_ALG {
+            anyhow::bail!("Invalid or unsupported minisign public key");
+        }
+        Ok(Self {
+            keynum: data[2..2 + KEYNUM_LEN].try_into()?,
+            key: VerifyingKey::from_bytes(data[2 + KEYNUM_LEN..].try_into()?)?,
+        })
+    }
+
+    fn encode(&self) -> String {
+        let mut data = SIG_ALG.to_vec();
+        data.extend_from_slice(&self.keynum);
+        data.extend_from_slice(self.key.as_bytes());
+        format!(
+            "{}minisign public key {}\n{}\n",
+            UNTRUSTED_PREFIX,
+            key_id(&self.keynum),
+            STANDARD.encode(data)
+        )
+    }
+}
+
+/// libsodium `crypto_pwhash_scryptsalsa208sha256` 由 opslimit / memlimit 推出 scrypt 参数的算法。
+fn scrypt_params(opslimit: u64, memlimit: u64) -> Result<scrypt::Params> {
+    let opslimit = opslimit.max(32768);
+    let r: u64 = 8;
+    let log_n = |max_n: u64| (1..63u8).find(|n| 1u64 << n > max_n / 2).unwrap_or(63);
+    let (log_n, p) = if opslimit < memlimit / 32 {
+        (log_n(opslimit / (r * 4)), 1)
+    } else {
+        let log_n = log_n(memlimit / (r * 128));
+        let max_rp = ((opslimit / 4) >> log_n).min(0x3fff_ffff);
+        (log_n, max_rp / r)
+    };
+    if log_n > MAX_SCRYPT_LOG_N {
+        anyhow::bail!("The minisign secret key uses an excessive scrypt work factor");
+    }
+    // `len` 只用于 PHC 字符串，输出长度由 `scrypt::scrypt` 的缓冲区决定
+    scrypt::Params::new(log_n, r as u32, p as u32, scrypt::Params::RECOMMENDED_LEN)
+        .map_err(|e| anyhow::anyhow!("Invalid scrypt parameters in minisign secret key: {}", e))
+}
+
+fn keynum_sk_stream(
+    passphrase: &str,
+    salt: &[u8],
+    opslimit: u64,
+    memlimit: u64,
+) -> Result<Zeroizing<[u8; KEYNUM_SK_LEN]>> {
+    let params = scrypt_params(opslimit, memlimit)?;
+    let mut stream = Zeroizing::new([0u8; KEYNUM_SK_LEN]);
+    scrypt::scrypt(passphrase.as_bytes(), salt, &params, &mut stream[..])
+        .map_err(|e| anyhow::anyhow!("scrypt failed: {}", e))?;
+    Ok(stream)
+}
+
+fn secret_checksum(keynum: &[u8], secret: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update(SIG_ALG);
+    hasher.update(keynum);
+    hasher.update(secret);
+    hasher.finalize().into()
+}
+
+impl SecretKey {
+    fn decode(text: &str, passphrase: PassphraseFn) -> Result<Self> {
+        let lines = decode_base64_lines(text)?;
+        let [data] = lines.as_slice() else {
+            anyhow::bail!("Invalid minisign secret key");
+        };
+        let data = Zeroizing::new(data.clone());
+        if data.len() != SECRET_KEY_LEN || &data[..2] != SIG_ALG || &data[4..6] != CHK_ALG {
+            anyhow::bail!("Invalid or unsupported minisign secret key");
+        }
+        let salt = &data[6..6 + SALT_LEN];
+        let limits = &data[6 + SALT_LEN..6 + SALT_LEN + 16];
+        let mut keynum_sk = Zeroizing::new([0u8; KEYNUM_SK_LEN]);
+        keynum_sk.copy_from_slice(&data[SECRET_KEY_LEN - KEYNUM_SK_LEN..]);
+        match &data[2..4] {
+            kdf if kdf == KDF_ALG => {
+                let opslimit = u64::from_le_bytes(limits[..8].try_into()?);
+                let memlimit = u64::from_le_bytes(limits[8..].try_into()?);
+                let stream = keynum_sk_stream(&passphrase()?, salt, opslimit, memlimit)?;
+                keynum_sk
+                    .iter_mut()
+                    .zip(stream.iter())
+                    .for_each(|(b, s)| *b ^= s);
+            }
+            kdf if kdf == KDF_NONE => {}
+            _ => anyhow::bail!("Unsupported minisign key derivation function"),
+        }
+        let (keynum, rest) = keynum_sk.split_at(KEYNUM_LEN);
+        let (secret, checksum) = rest.split_at(64);
+        if secret_checksum(keynum, secret) != *checksum {
+            anyhow::bail!("Wrong passphrase or corrupted minisign secret key");
+        }
+        let key = SigningKey::from_bytes(secret[..32].try_into()?);
+        if key.verifying_key().as_bytes() != &secret[32..] {
+            anyhow::bail!("Corrupted minisign secret key: public key mismatch");
+        }
+        Ok(Self {
+            keynum: keynum.try_into()?,
+            key,
+        })
+    }
+
+    /// `passphrase` 为 `None` 时生成未加密的私钥（相当于 `minisign -G -W`）。
+    fn encode(&self, passphrase: Option<&str>) -> Result<Zeroizing<String>> {
+        self.encode_with(passphrase, DEFAULT_OPSLIMIT, DEFAULT_MEMLIMIT)
+    }
+
+    fn encode_with(
+        &self,
+        passphrase: Option<&str>,
+        opslimit: u64,
+        memlimit: u64,
+    ) -> Result<Zeroizing<String>> {
+        let mut secret = Zeroizing::new(self.key.to_bytes().to_vec());
+        secret.extend_from_slice(self.key.verifying_key().as_bytes());
+        let mut keynum_sk = Zeroizing::new(self.keynum.to_vec());
+        keynum_sk.extend_from_slice(&secret);
+        keynum_sk.extend_from_slice(&secret_checksum(&self.keynum, &secret));
+
+        let mut salt = [0u8; SALT_LEN];
+        let mut data = Zeroizing::new(SIG_ALG.to_vec());
+        match passphrase {
+            Some(passphrase) => {
+                OsRng.fill_bytes(&mut salt);
+                let stream = keynum_sk_stream(passphrase, &salt, opslimit, memlimit)?;
+                keynum_sk
+                    .iter_mut()
+                    .zip(stream.iter())
+                    .for_each(|(b, s)| *b ^= s);
+                data.extend_from_slice(KDF_ALG);
+            }
+            None => data.extend_from_slice(KDF_NONE),
+        }
+        data.extend_from_slice(CHK_ALG);
+        data.extend_from_slice(&salt);
+        data.extend_from_slice(&opslimit.to_le_bytes());
+        data.extend_from_slice(&memlimit.to_le_bytes());
+        data.extend_from_slice(&keynum_sk);
+        let comment = if passphrase.is_some() {
+            "minisign encrypted secret key"
+        } else {
+            "minisign unencrypted secret key"
+        };
+        Ok(Zeroizing::new(format!(
+            "{}{}\n{}\n",
+            UNTRUSTED_PREFIX,
+            comment,
+            STANDARD.encode(&data)
+        )))
+    }
+}
+
+impl MinisignSignature {
+    fn decode(text: &str) -> Result<Self> {
+        let mut lines = text.lines();
+        let mut next = || lines.next().context("Truncated minisign signature");
+        if !next()?.starts_with(UNTRUSTED_PREFIX) {
+            anyhow::bail!("Invalid minisign signature: missing untrusted comment");
+        }
+        let data = STANDARD.decode(next()?.trim())?;
+        let trusted_comment = next()?
+            .strip_prefix(TRUSTED_PREFIX)
+            .context("Invalid minisign signature: missing trusted comment")?
+            .to_string();
+        let global_signature = STANDARD.decode(next()?.trim())?;
+        if data.len() != SIGNATURE_LEN {
+            anyhow::bail!("Invalid minisign signature length");
+        }
+        let prehashed = match &data[..2] {
+            alg if alg == SIG_ALG_PREHASHED => true,
+            alg if alg == SIG_ALG => false,
+            _ => anyhow::bail!("Unsupported minisign signature algorithm"),
+        };
+        Ok(Self {
+            prehashed,
+            keynum: data[2..2 + KEYNUM_LEN].try_into()?,
+            signature: Signature::from_slice(&data[2 + KEYNUM_LEN..])?,
+            trusted_comment,
+            global_signature: Signature::from_slice(&global_signature)?,
+        })
+    }
+
+    fn encode(&self) -> String {
+        let alg = if self.prehashed {
+            SIG_ALG_PREHASHED
+        } else {
+            SIG_ALG
+        };
+        let mut data = alg.to_vec();
+        data.extend_from_slice(&self.keynum);
+        data.extend_from_slice(&self.signature.to_bytes());
+        format!(
+            "{}signature from minisign secret key\n{}\n{}{}\n{}\n",
+            UNTRUSTED_PREFIX,
+            STANDARD.encode(data),
+            TRUSTED_PREFIX,
+            self.trusted_comment,
+            STANDARD.encode(self.global_signature.to_bytes())
+        )
+    }
+
+    /// 全局签名覆盖签名本身与可信注释。
+    fn global_message(signature: &Signature, trusted_comment: &str) -> Vec<u8> {
+        let mut message = signature.to_bytes().to_vec();
+        message.extend_from_slice(trusted_comment.as_bytes());
+        message
+    }
+}
+
+/// 流式计算 BLAKE2b-512 预哈希。
+fn blake2b_prehash<R: Read>(mut reader: R) -> Result<[u8; 64]> {
+    let mut hasher = Blake2b512::new();
+    io::copy(&mut reader, &mut hasher)?;
+    Ok(hasher.finalize().into())
+}
+
+/// 生成 minisign 密钥对，返回 `[私钥文件, 公钥文件]` 的内容。
+pub fn minisign_keygen(passphrase: Option<&str>) -> Result<Vec<Vec<u8>>> {
+    let mut keynum = [0u8; KEYNUM_LEN];
+    OsRng.fill_bytes(&mut keynum);
+    let secret = SecretKey {
+        keynum,
+        key: SigningKey::generate(&mut OsRng),
+    };
+    let public = PublicKey {
+        keynum,
+        key: secret.key.verifying_key(),
+    };
+    Ok(vec![
+        secret.encode(passphrase)?.as_bytes().to_vec(),
+        public.encode().into_bytes(),
+    ])
+}
+
+/// 以预哈希模式（`ED`）签名，返回 `.minisig` 文件内容。
+/// 未指定可信注释时与 minisign 相同，记录时间戳与文件名。
+pub fn process_minisign_sign(
+    input: &str,
+    key: &str,
+    trusted_comment: Option<&str>,
+) -> Result<String> {
+    let text = Zeroizing::new(fs::read_to_string(key)?);
+    let secret = SecretKey::decode(&text, &prompt_passphrase)
+        .with_context(|| format!("Failed to load minisign secret key {}", key))?;
+    sign(
+        get_reader(input)?,
+        &secret,
+        &trusted_comment_for(input, trusted_comment)?,
+    )
+}
+
+fn trusted_comment_for(input: &str, trusted_comment: Option<&str>) -> Result<String> {
+    let comment = match trusted_comment {
+        Some(comment) => comment.to_string(),
+        None => {
+            let file = Path::new(input)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| input.to_string());
+            let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            format!("timestamp:{}\tfile:{}\tprehashed", timestamp, file)
+        }
+    };
+    if comment.contains(['\r', '\n']) {
+        anyhow::bail!("The trusted comment must be a single line");
+    }
+    Ok(comment)
+}
+
+fn sign<R: Read>(reader: R, secret: &SecretKey, trusted_comment: &str) -> Result<String> {
+    let signature = secret.key.sign(&blake2b_prehash(reader)?);
+    let global_signature = secret.key.sign(&MinisignSignature::global_message(
+        &signature,
+        trusted_comment,
+    ));
+    Ok(MinisignSignature {
+        prehashed: true,
+        keynum: secret.keynum,
+        signature,
+        trusted_comment: trusted_comment.to_string(),
+        global_signature,
+    }
+    .encode())
+}
+
+/// 验证 `.minisig` 签名（预哈希与旧版模式均支持），成功时返回可信注释。
+/// 签名或可信注释的签名无效时返回 `None`；key id 不符时报错。
+pub fn process_minisign_verify(input: &str, key: &str, signature: &str) -> Result<Option<String>> {
+    let public = PublicKey::decode(&fs::read_to_string(key)?)
+        .with_context(|| format!("Failed to load minisign public key {}", key))?;
+    verify(get_reader(input)?, &public, signature)
+}
+
+fn verify<R: Read>(mut reader: R, public: &PublicKey, signature: &str) -> Result<Option<String>> {
+    let signature = MinisignSignature::decode(signature)?;
+    if signature.keynum != public.keynum {
+        anyhow::bail!(
+            "The signature was made with minisign key {} but the given key is {}",
+            key_id(&signature.keynum),
+            key_id(&public.keynum)
+        );
+    }
+    let valid = if signature.prehashed {
+        let digest = blake2b_prehash(reader)?;
+        public.key.verify_strict(&digest, &signature.signature)
+    } else {
+        // 旧版签名直接对原文签名，需要把输入整个读入内存
+        let mut message = Vec::new();
+        reader.read_to_end(&mut message)?;
+        public.key.verify_strict(&message, &signature.signature)
+    };
+    if valid.is_err() {
+        return Ok(None);
+    }
+    let global =
+        MinisignSignature::global_message(&signature.signature, &signature.trusted_comment);
+    if public
+        .key
+        .verify_strict(&global, &signature.global_signature)
+        .is_err()
+    {
+        return Ok(None);
+    }
+    Ok(Some(signature.trusted_comment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> String {
+        fs::read_to_string(format!("fixtures/minisign/{}", name)).unwrap()
+    }
+
+    fn passphrase() -> Result<Zeroizing<String>> {
+        Ok(Zeroizing::new("rcli".to_string()))
+    }
+
+    #[test]
+    fn test_scrypt_params_match_libsodium() {
+        let params = scrypt_params(DEFAULT_OPSLIMIT, DEFAULT_MEMLIMIT).unwrap();
+        assert_eq!((params.log_n(), params.r(), params.p()), (20, 8, 1));
+        assert!(scrypt_params(DEFAULT_OPSLIMIT * 4, DEFAULT_MEMLIMIT * 4).is_err());
+    }
+
+    #[test]
+    fn test_minisign_known_vectors() -> Result<()> {
+        // minisign 生成的预哈希与旧版签名
+        let public = PublicKey::decode(&fixture("test.pub"))?;
+        assert_eq!(key_id(&public.keynum), "E7620F1842B4E81F");
+        let comment = verify(&b"test"[..], &public, &fixture("test.minisig"))?;
+        assert_eq!(comment.as_deref(), Some("timestamp:1556193335\tfile:test"));
+        let comment = verify(&b"test"[..], &public, &fixture("test.legacy.minisig"))?;
+        assert_eq!(comment.as_deref(), Some("timestamp:1555779966\tfile:test"));
+
+        assert_eq!(
+            verify(&b"tesT"[..], &public, &fixture("test.minisig"))?,
+            None
+        );
+        let forged = fixture("test.minisig").replace("file:test", "file:evil");
+        assert_eq!(verify(&b"test"[..], &public, &forged)?, None);
+        let other = PublicKey::decode(&fixture("python.pub"))?;
+        assert!(verify(&b"test"[..], &other, &fixture("test.minisig")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_minisign_encrypted_secret_key() -> Result<()> {
+        // 独立实现生成的加密私钥（小代价的 scrypt 参数，口令为 rcli）
+        let secret = SecretKey::decode(&fixture("python.key"), &passphrase)?;
+        let public = PublicKey::decode(&fixture("python.pub"))?;
+        assert_eq!(secret.keynum, public.keynum);
+        assert_eq!(secret.key.verifying_key(), public.key);
+        assert!(
+            SecretKey::decode(&fixture("python.key"), &|| Ok(Zeroizing::new(
+                "wrong".to_string()
+            )))
+            .is_err()
+        );
+
+        let comment = verify(&b"test"[..], &public, &fixture("python.minisig"))?;
+        assert_eq!(
+            comment.as_deref(),
+            Some("timestamp:1760000000\tfile:test\tprehashed")
+        );
+        // Ed25519 签名是确定性的，重新签名与 fixture 完全一致
+        let signature = sign(
+            &b"test"[..],
+            &secret,
+            "timestamp:1760000000\tfile:test\tprehashed",
+        )?;
+        assert_eq!(signature, fixture("python.minisig"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_minisign_roundtrip() -> Result<()> {
+        let keys = minisign_keygen(None)?;
+        let secret = SecretKey::decode(std::str::from_utf8(&keys[0])?, &|| {
+            anyhow::bail!("unencrypted keys need no passphrase")
+        })?;
+        let public = PublicKey::decode(std::str::from_utf8(&keys[1])?)?;
+
+        let encrypted = secret.encode_with(Some("rcli"), 32768, 32768 * 32 + 1)?;
+        let decrypted = SecretKey::decode(&encrypted, &passphrase)?;
+        assert_eq!(decrypted.key.to_bytes(), secret.key.to_bytes());
+
+        let comment = trusted_comment_for("dist/app.tar.gz", None)?;
+        assert!(
+            comment.ends_with("\tfile:app.tar.gz\tprehashed"),
+            "{}",
+            comment
+        );
+        assert!(trusted_comment_for("x", Some("a\nb")).is_err());
+        let signature = sign(&b"hello"[..], &decrypted, &comment)?;
+        assert_eq!(verify(&b"hello"[..], &public, &signature)?, Some(comment));
+        Ok(())
+    }
+}
@@ -1,13 +1,45 @@
+mod age;
 mod b64;
+mod codec;
 mod csv_convert;
+mod derive;
+mod encrypt;
 mod gen_pass;
+mod hash;
 mod http_serve;
 mod jwt;
+mod key;
+mod keyring;
+mod manifest;
+mod minisign;
+mod otp;
+mod pkey;
 mod text;
 
+pub use age::{
+    process_age_decrypt, process_age_encrypt, process_age_keygen, read_age_identities,
+    read_age_recipients, AgeError, AgeIdentity, AgeRecipient, AGE_DEFAULT_WORK_FACTOR,
+    AGE_MAX_WORK_FACTOR,
+};
 pub use b64::{process_decode, process_encode};
+pub use codec::{process_codec_decode, process_codec_encode};
 pub use csv_convert::process_csv;
+pub use derive::process_derive;
+pub use encrypt::{process_decrypt, process_encrypt, read_passphrase, EncryptionKey};
 pub use gen_pass::process_gen_pass;
+pub use hash::{hash_reader, process_hash, process_hash_check};
 pub use http_serve::process_http_server;
 pub use jwt::{process_create_jwt_token, process_verify_jwt_token};
-pub use text::{create_key, process_sign, process_verify};
+pub use key::{allow_legacy_keys, KeyFile, KeyKind};
+pub use keyring::{Keyring, KeyringEntry, KEYRING_DIR_ENV};
+pub use manifest::{
+    manifest_signature_path, process_sign_dir, process_verify_dir, DirVerifyReport,
+    DIR_MANIFEST_NAME,
+};
+pub use minisign::{is_minisign, minisign_keygen, process_minisign_sign, process_minisign_verify};
+pub use otp::{process_otp_generate, process_otp_new, process_otp_verify, read_otp_secret, Otp};
+pub use pkey::{encode_pkcs8_pem, encode_spki_pem};
+pub use text::{
+    create_key, process_sign, process_sign_detached, process_verify, process_verify_detached,
+    SignatureFile,
+};
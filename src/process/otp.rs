@@ -0,0 +1,257 @@
+use std::{
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use hmac::{digest::KeyInit, Hmac, Mac};
+use percent_encoding::utf8_percent_encode;
+use rand::RngCore;
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use subtle::ConstantTimeEq;
+
+use super::codec::URI_COMPONENT;
+use crate::cli::otp_opts::OtpAlgorithm;
+
+const SECRET_ALPHABET: base32::Alphabet = base32::Alphabet::Rfc4648 { padding: false };
+
+pub struct Otp {
+    secret: Vec<u8>,
+    algorithm: OtpAlgorithm,
+    digits: u32,
+    period: u64,
+}
+
+impl Otp {
+    pub fn new(secret: Vec<u8>, algorithm: OtpAlgorithm, digits: u32, period: u64) -> Self {
+        Self {
+            secret,
+            algorithm,
+            digits,
+            period,
+        }
+    }
+
+    /// 从 base32 编码的密钥创建，忽略空格、大小写与 `=` 填充。
+    pub fn try_new(
+        secret: &str,
+        algorithm: OtpAlgorithm,
+        digits: u32,
+        period: u64,
+    ) -> Result<Self> {
+        if !(6..=9).contains(&digits) {
+            anyhow::bail!("OTP digits must be between 6 and 9, got {}", digits);
+        }
+        if period == 0 {
+            anyhow::bail!("OTP period must be greater than 0");
+        }
+        let secret = decode_secret(secret)?;
+        Ok(Self::new(secret, algorithm, digits, period))
+    }
+
+    /// RFC 4226 HOTP
+    pub fn hotp(&self, counter: u64) -> Result<String> {
+        let digest = self.hmac(&counter.to_be_bytes())?;
+        let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+        let code = u32::from_be_bytes(digest[offset..offset + 4].try_into()?) & 0x7fff_ffff;
+        let code = code % 10u32.pow(self.digits);
+        Ok(format!("{:0width$}", code, width = self.digits as usize))
+    }
+
+    /// RFC 6238 TOTP
+    pub fn totp(&self, timestamp: u64) -> Result<String> {
+        self.hotp(timestamp / self.period)
+    }
+
+    /// 在 `counter..=counter + window` 范围内查找匹配的计数器。
+    pub fn verify_hotp(&self, code: &str, counter: u64, window: u64) -> Result<Option<u64>> {
+        for c in counter..=counter.saturating_add(window) {
+            if bool::from(self.hotp(c)?.as_bytes().ct_eq(code.as_bytes())) {
+                return Ok(Some(c));
+            }
+        }
+        Ok(None)
+    }
+
+    /// 允许前后 `window` 个时间步的时钟漂移，返回匹配的时间步。
+    pub fn verify_totp(&self, code: &str, timestamp: u64, window: u64) -> Result<Option<u64>> {
+        let step = timestamp / self.period;
+        let start = step.saturating_sub(window);
+        self.verify_hotp(code, start, step.saturating_add(window) - start)
+    }
+
+    fn hmac(&self, msg: &[u8]) -> Result<Vec<u8>> {
+        match self.algorithm {
+            OtpAlgorithm::Sha1 => hmac_digest::<Hmac<Sha1>>(&self.secret, msg),
+            OtpAlgorithm::Sha256 => hmac_digest::<Hmac<Sha256>>(&self.secret, msg),
+            OtpAlgorithm::Sha512 => hmac_digest::<Hmac<Sha512>>(&self.secret, msg),
+        }
+    }
+}
+
+fn hmac_digest<M: Mac + KeyInit>(key: &[u8], msg: &[u8]) -> Result<Vec<u8>> {
+    let mut mac = <M as KeyInit>::new_from_slice(key).map_err(|e| anyhow::anyhow!(e))?;
+    mac.update(msg);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+fn decode_secret(secret: &str) -> Result<Vec<u8>> {
+    let secret: String = secret
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '=')
+        .collect::<String>()
+        .to_uppercase();
+    let secret = base32::decode(SECRET_ALPHABET, &secret).context("Invalid base32 OTP secret")?;
+    if secret.is_empty() {
+        anyhow::bail!("OTP secret is empty");
+    }
+    Ok(secret)
+}
+
+fn now() -> Result<u64> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+}
+
+/// 从 `--secret` 或 `--key` 文件中读取 base32 密钥。
+pub fn read_otp_secret(secret: Option<String>, key: Option<String>) -> Result<String> {
+    match (secret, key) {
+        (Some(secret), _) => Ok(secret),
+        (None, Some(path)) => Ok(fs::read_to_string(path)?.trim().to_string()),
+        (None, None) => anyhow::bail!("Either --secret or --key must be provided"),
+    }
+}
+
+pub fn process_otp_generate(
+    otp: &Otp,
+    counter: Option<u64>,
+    timestamp: Option<u64>,
+) -> Result<String> {
+    match counter {
+        Some(counter) => otp.hotp(counter),
+        None => otp.totp(timestamp.map_or_else(now, Ok)?),
+    }
+}
+
+/// 验证一次性密码，返回匹配的计数器（HOTP）或时间步（TOTP），未匹配时返回 `None`。
+/// HOTP 在向前查找匹配后，调用方应将计数器保存为匹配值加一以重新同步（RFC 4226 7.2）。
+pub fn process_otp_verify(
+    otp: &Otp,
+    code: &str,
+    counter: Option<u64>,
+    timestamp: Option<u64>,
+    window: u64,
+) -> Result<Option<u64>> {
+    let code = code.trim();
+    match counter {
+        Some(counter) => otp.verify_hotp(code, counter, window),
+        None => otp.verify_totp(code, timestamp.map_or_else(now, Ok)?, window),
+    }
+}
+
+/// 生成新的随机密钥（长度与哈希输出一致）并返回 `(base32 密钥, otpauth URI)`。
+pub fn process_otp_new(
+    issuer: &str,
+    account: &str,
+    algorithm: OtpAlgorithm,
+    digits: u32,
+    period: u64,
+    counter: Option<u64>,
+) -> Result<(String, String)> {
+    let mut secret = vec![0u8; algorithm.key_len()];
+    rand::rngs::OsRng.fill_bytes(&mut secret);
+    let secret = base32::encode(SECRET_ALPHABET, &secret);
+
+    let label = format!(
+        "{}:{}",
+        utf8_percent_encode(issuer, URI_COMPONENT),
+        utf8_percent_encode(account, URI_COMPONENT)
+    );
+    let mut uri = format!(
+        "otpauth://{}/{}?secret={}&issuer={}&algorithm={}&digits={}",
+        if counter.is_some() { "hotp" } else { "totp" },
+        label,
+        secret,
+        utf8_percent_encode(issuer, URI_COMPONENT),
+        algorithm.to_string().to_uppercase(),
+        digits,
+    );
+    match counter {
+        Some(counter) => uri.push_str(&format!("&counter={}", counter)),
+        None => uri.push_str(&format!("&period={}", period)),
+    }
+    Ok((secret, uri))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rfc_secret(len: usize) -> String {
+        let secret: Vec<u8> = b"1234567890".iter().cycle().take(len).copied().collect();
+        base32::encode(SECRET_ALPHABET, &secret)
+    }
+
+    #[test]
+    fn test_hotp_rfc4226() {
+        let otp = Otp::try_new(&rfc_secret(20), OtpAlgorithm::Sha1, 6, 30).unwrap();
+        let expected = [
+            "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583",
+            "399871", "520489",
+        ];
+        for (counter, code) in expected.iter().enumerate() {
+            assert_eq!(otp.hotp(counter as u64).unwrap(), *code);
+        }
+    }
+
+    #[test]
+    fn test_totp_rfc6238() {
+        let cases = [
+            (OtpAlgorithm::Sha1, 20, 59, "94287082"),
+            (OtpAlgorithm::Sha256, 32, 59, "46119246"),
+            (OtpAlgorithm::Sha512, 64, 59, "90693936"),
+            (OtpAlgorithm::Sha1, 20, 1111111109, "07081804"),
+            (OtpAlgorithm::Sha256, 32, 1234567890, "91819424"),
+            (OtpAlgorithm::Sha512, 64, 20000000000, "47863826"),
+        ];
+        for (algorithm, len, time, code) in cases {
+            let otp = Otp::try_new(&rfc_secret(len), algorithm, 8, 30).unwrap();
+            assert_eq!(otp.totp(time).unwrap(), code);
+        }
+    }
+
+    #[test]
+    fn test_verify_with_window() {
+        let otp = Otp::try_new(&rfc_secret(20), OtpAlgorithm::Sha1, 6, 30).unwrap();
+        let code = otp.totp(1000).unwrap();
+        assert_eq!(
+            process_otp_verify(&otp, &code, None, Some(1030), 1).unwrap(),
+            Some(33)
+        );
+        assert_eq!(
+            process_otp_verify(&otp, &code, None, Some(1090), 1).unwrap(),
+            None
+        );
+        assert_eq!(
+            process_otp_verify(&otp, "287082", Some(0), None, 1).unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            process_otp_verify(&otp, "359152", Some(0), None, 1).unwrap(),
+            None
+        );
+        assert_eq!(
+            process_otp_verify(&otp, "969429", Some(1), None, 2).unwrap(),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn test_process_otp_new() {
+        let (secret, uri) =
+            process_otp_new("ACME Co", "ops@acme", OtpAlgorithm::Sha256, 6, 30, None).unwrap();
+        assert_eq!(decode_secret(&secret).unwrap().len(), 32);
+        assert!(uri.starts_with("otpauth://totp/ACME%20Co:ops%40acme?secret="));
+        assert!(uri.ends_with("&issuer=ACME%20Co&algorithm=SHA256&digits=6&period=30"));
+    }
+}
@@ -1,17 +1,36 @@
-use std::fs;
-use std::io::Read;
+use std::{
+    fmt, fs,
+    io::{self, Read},
+    marker::PhantomData,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use crate::{cli::text::TextSignFormat, get_reader, process_gen_pass};
-use anyhow::{Context, Result};
-use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
-use chacha20poly1305::{
-    aead::{Aead, KeyInit},
-    AeadCore, ChaCha20Poly1305,
+use super::{
+    derive::X25519,
+    encrypt::ChaCha20,
+    key::{key_fingerprint, sha256_fingerprint, KeyFile, KeyKind},
+    minisign::{minisign_keygen, process_minisign_sign, process_minisign_verify},
+    pkey::{
+        ecdsa_signature_to_der, EcdsaP256Signer, EcdsaP256Verifier, EcdsaSecp256k1Signer,
+        EcdsaSecp256k1Verifier, PublicFingerprint, RsaPssSigner, RsaPssVerifier,
+    },
 };
-use ed25519_dalek::{
-    ed25519::signature::SignerMut, SecretKey, Signature, SigningKey, VerifyingKey,
+use crate::{
+    cli::text::{EcdsaEncoding, SignatureEncoding, TextSignFormat},
+    get_reader,
 };
-
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ed25519_dalek::{Signature, SigningKey, VerifyingKey};
+use hmac::{digest::KeyInit, Hmac, Mac};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use zeroize::Zeroizing;
+
+/// 签名与验证均以流的方式读取输入（`io::copy` 使用固定大小的缓冲区），
+/// 内存占用与输入大小无关。
 pub trait TextSign {
     fn sign<R: Read>(&self, reader: R) -> Result<Vec<u8>>;
 }
@@ -38,47 +57,45 @@ impl Blake3 {
     pub fn new(key: [u8; 32]) -> Self {
         Self { key }
     }
+}
 
-    pub fn try_new(key: &[u8]) -> Result<Self> {
-        let key = &key[..32];
-        let key: [u8; 32] = key.try_into()?;
-        Ok(Self::new(key))
+impl Blake3 {
+    fn keyed_hash<R: Read>(&self, mut reader: R) -> Result<blake3::Hash> {
+        let mut hasher = blake3::Hasher::new_keyed(&self.key);
+        io::copy(&mut reader, &mut hasher)?;
+        Ok(hasher.finalize())
     }
 }
 
 impl TextSign for Blake3 {
-    fn sign<R: Read>(&self, mut reader: R) -> Result<Vec<u8>> {
-        let mut buf = Vec::new();
-        reader.read_to_end(&mut buf)?;
-
-        let hash = blake3::keyed_hash(&self.key, &buf);
-
+    fn sign<R: Read>(&self, reader: R) -> Result<Vec<u8>> {
+        let hash = self.keyed_hash(reader)?;
         Ok(hash.as_bytes().to_vec())
     }
 }
 
 impl TextVerify for Blake3 {
-    fn verify<R: Read>(&self, mut reader: R, signature: &[u8]) -> Result<bool> {
-        let mut buf = Vec::new();
-        reader.read_to_end(&mut buf)?;
-        let hash = blake3::keyed_hash(&self.key, &buf);
-
-        Ok(hash.as_bytes().to_vec() == signature.to_vec())
+    /// `blake3::Hash` 的相等比较是常数时间的，长度不对的签名直接视为无效。
+    fn verify<R: Read>(&self, reader: R, signature: &[u8]) -> Result<bool> {
+        let hash = self.keyed_hash(reader)?;
+        Ok(<[u8; blake3::OUT_LEN]>::try_from(signature)
+            .is_ok_and(|signature| hash == blake3::Hash::from(signature)))
     }
 }
 
 impl KeyLoader for Blake3 {
     fn load_key(path: &str) -> Result<Self> {
-        let key = fs::read(path)?;
-        Self::try_new(&key)
+        let key = KeyFile::load(path)?.key_for(KeyKind::Blake3)?;
+        Ok(Self::new(*key))
     }
 }
 
 impl KeyGenerator for Blake3 {
+    /// 直接使用系统 CSPRNG 的 32 字节作为密钥。
     fn generate() -> Result<Vec<Vec<u8>>> {
-        let key = process_gen_pass(32, true, true, true, true)?;
-        let key = key.into_bytes();
-        Ok(vec![key])
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+        Ok(vec![key.to_vec()])
     }
 }
 
@@ -91,170 +108,378 @@ impl Ed25519 {
         Self { key }
     }
 
-    pub fn try_new(key: &[u8]) -> Result<Self> {
-        let key = &key[..32];
-        let key: [u8; 32] = key.try_into()?;
-        Ok(Self::new(key))
+    /// 签名需要私钥，验证需要公钥，类型不符时返回错误。
+    fn load(path: &str, kind: KeyKind) -> Result<Self> {
+        let key = KeyFile::load(path)?.key_for(kind)?;
+        Ok(Self::new(*key))
     }
 }
 
+/// 使用 Ed25519ph（RFC 8032）：先对输入做 SHA-512 预哈希，再对摘要签名，
+/// 因此无需把整个输入读入内存。
+fn prehash<R: Read>(mut reader: R) -> Result<Sha512> {
+    let mut hasher = Sha512::new();
+    io::copy(&mut reader, &mut hasher)?;
+    Ok(hasher)
+}
+
 impl TextSign for Ed25519 {
-    fn sign<R: Read>(&self, mut reader: R) -> Result<Vec<u8>> {
-        let mut buf = Vec::new();
-        reader.read_to_end(&mut buf)?;
-        let mut key = SigningKey::from_bytes(&self.key);
-        let signature: Signature = key.sign(&buf);
+    fn sign<R: Read>(&self, reader: R) -> Result<Vec<u8>> {
+        let key = SigningKey::from_bytes(&self.key);
+        let signature: Signature = key.sign_prehashed(prehash(reader)?, None)?;
         Ok(signature.to_bytes().to_vec())
     }
 }
 
 impl TextVerify for Ed25519 {
-    fn verify<R: Read>(&self, mut reader: R, signature: &[u8]) -> Result<bool> {
-        let mut buf = Vec::new();
-        reader.read_to_end(&mut buf)?;
-
+    fn verify<R: Read>(&self, reader: R, signature: &[u8]) -> Result<bool> {
         let verifying_key: VerifyingKey = VerifyingKey::from_bytes(&self.key)?;
 
-        let signature = signature.try_into()?;
+        let Ok(signature) = signature.try_into() else {
+            return Ok(false);
+        };
         let signature = Signature::from_bytes(signature);
 
-        Ok(verifying_key.verify_strict(&buf, &signature).is_ok())
-    }
-}
-
-impl KeyLoader for Ed25519 {
-    fn load_key(path: &str) -> Result<Self> {
-        let key = fs::read(path)?;
-        Self::try_new(&key)
+        Ok(verifying_key
+            .verify_prehashed_strict(prehash(reader)?, None, &signature)
+            .is_ok())
     }
 }
 
 impl KeyGenerator for Ed25519 {
     fn generate() -> Result<Vec<Vec<u8>>> {
-        let secret_key =
-            process_gen_pass(32, true, true, true, true).context("Failed to generate key")?;
-        let secret_key: SecretKey = secret_key.as_bytes().try_into().context("Invalid key")?;
-        let signing_key = SigningKey::from_bytes(&secret_key);
-        let verifying_key = signing_key.verifying_key();
-        let verifying_key = verifying_key.to_bytes().to_vec();
-        let secret_key = secret_key.to_vec();
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let verifying_key = signing_key.verifying_key().to_bytes().to_vec();
+        let secret_key = signing_key.to_bytes().to_vec();
         Ok(vec![secret_key, verifying_key])
     }
 }
 
-struct ChaCha20 {
-    key: [u8; 32],
-    nonce: [u8; 12],
+/// HMAC 签名，`M` 为 `Hmac<Sha256>` 等具体的 MAC 类型。
+/// 密钥长度不限，可以直接使用 webhook 提供方给出的共享密钥。
+struct HmacKey<M> {
+    key: Zeroizing<Vec<u8>>,
+    mac: PhantomData<M>,
 }
 
-impl ChaCha20 {
-    pub fn new(key: [u8; 32], nonce: [u8; 12]) -> Self {
-        Self { key, nonce }
+impl<M: Mac + KeyInit> HmacKey<M> {
+    pub fn new(key: &[u8]) -> Self {
+        Self {
+            key: Zeroizing::new(key.to_vec()),
+            mac: PhantomData,
+        }
     }
 
-    pub fn try_new(key: &[u8], nonce: &[u8]) -> Result<Self> {
-        let key = &key[..32];
-        let key: [u8; 32] = key.try_into()?;
-        let nonce = &nonce[..12];
-        let nonce: [u8; 12] = nonce.try_into()?;
-        Ok(Self::new(key, nonce))
+    fn mac<R: Read>(&self, mut reader: R) -> Result<M> {
+        let mut mac = <M as Mac>::new_from_slice(&self.key)
+            .map_err(|e| anyhow::anyhow!("Invalid HMAC key: {}", e))?;
+        let mut buf = [0u8; 8192];
+        loop {
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                return Ok(mac);
+            }
+            mac.update(&buf[..n]);
+        }
     }
 }
 
-impl TextSign for ChaCha20 {
-    fn sign<R: Read>(&self, mut reader: R) -> Result<Vec<u8>> {
-        let mut buf = Vec::new();
-        reader.read_to_end(&mut buf)?;
-        let key = chacha20poly1305::Key::from_slice(&self.key[..32]);
-        let nonce = chacha20poly1305::Nonce::from_slice(&self.nonce);
-        let cipher = ChaCha20Poly1305::new(key);
-        let ciphertext = cipher
-            .encrypt(nonce, buf.as_ref())
-            .map_err(|e| anyhow::anyhow!(e))?;
-        Ok(ciphertext)
+impl<M: Mac + KeyInit> TextSign for HmacKey<M> {
+    fn sign<R: Read>(&self, reader: R) -> Result<Vec<u8>> {
+        Ok(self.mac(reader)?.finalize().into_bytes().to_vec())
     }
 }
 
-impl TextVerify for ChaCha20 {
-    fn verify<R: Read>(&self, mut reader: R, signature: &[u8]) -> Result<bool> {
-        let mut buf = Vec::new();
-        reader.read_to_end(&mut buf)?;
-        let key = chacha20poly1305::Key::from_slice(&self.key[..32]);
-        let nonce = chacha20poly1305::Nonce::from_slice(&self.nonce);
-        let cipher = ChaCha20Poly1305::new(key);
-        let plaintext = cipher
-            .decrypt(nonce, signature.as_ref())
-            .map_err(|e| anyhow::anyhow!(e))?;
-        println!("{}", String::from_utf8_lossy(&plaintext));
-        Ok(true)
+impl<M: Mac + KeyInit> TextVerify for HmacKey<M> {
+    /// `verify_slice` 以常数时间比较，长度不同时直接失败。
+    fn verify<R: Read>(&self, reader: R, signature: &[u8]) -> Result<bool> {
+        Ok(self.mac(reader)?.verify_slice(signature).is_ok())
     }
 }
 
-impl KeyLoader for ChaCha20 {
+impl<M: Mac + KeyInit> KeyLoader for HmacKey<M> {
+    /// `text generate` 生成的密钥文件按类型加载；其他文件（没有类型）的内容本身就是共享密钥，
+    /// 去掉末尾的换行后按原样使用，与 webhook 提供方计算签名的方式一致。
     fn load_key(path: &str) -> Result<Self> {
-        let read_data = fs::read(path)?;
-        let key = &read_data[..32];
-        let nonce = &read_data[32..];
-        Self::try_new(key, nonce)
+        let data = Zeroizing::new(fs::read(path)?);
+        let key =
+            KeyFile::decode(&data).with_context(|| format!("Failed to load key file {}", path))?;
+        if key.kind().is_some() {
+            return Ok(Self::new(&key.key_for(KeyKind::Hmac)?[..]));
+        }
+        let secret = data.trim_ascii_end();
+        if secret.is_empty() {
+            anyhow::bail!("The HMAC key file {} is empty", path);
+        }
+        Ok(Self::new(secret))
     }
 }
 
-impl KeyGenerator for ChaCha20 {
+impl<M> KeyGenerator for HmacKey<M> {
+    /// 生成 32 字节的随机密钥，三种哈希算法通用。
     fn generate() -> Result<Vec<Vec<u8>>> {
-        let key = ChaCha20Poly1305::generate_key(&mut chacha20poly1305::aead::OsRng);
-        let nonce = ChaCha20Poly1305::generate_nonce(&mut chacha20poly1305::aead::OsRng);
-        let key = key.as_slice().into();
-        let nonce = nonce.as_slice().into();
-        Ok(vec![key, nonce])
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+        Ok(vec![key.to_vec()])
+    }
+}
+
+fn sign_hmac<M: Mac + KeyInit, R: Read>(reader: R, key: &str) -> Result<(Vec<u8>, String)> {
+    let hmac = HmacKey::<M>::load_key(key)?;
+    Ok((hmac.sign(reader)?, sha256_fingerprint(&hmac.key)))
+}
+
+fn verify_hmac<M: Mac + KeyInit, R: Read>(
+    reader: R,
+    key: &str,
+    signature: &[u8],
+    check: impl Fn(String) -> Result<()>,
+) -> Result<bool> {
+    let hmac = HmacKey::<M>::load_key(key)?;
+    check(sha256_fingerprint(&hmac.key))?;
+    hmac.verify(reader, signature)
+}
+
+fn sign_pkey<K: KeyLoader + TextSign + PublicFingerprint, R: Read>(
+    reader: R,
+    key: &str,
+) -> Result<(Vec<u8>, String)> {
+    let signer = K::load_key(key)?;
+    Ok((signer.sign(reader)?, signer.fingerprint()?))
+}
+
+fn verify_pkey<K: KeyLoader + TextVerify + PublicFingerprint, R: Read>(
+    reader: R,
+    key: &str,
+    signature: &[u8],
+    check: impl Fn(String) -> Result<()>,
+) -> Result<bool> {
+    let verifier = K::load_key(key)?;
+    check(verifier.fingerprint()?)?;
+    verifier.verify(reader, signature)
+}
+
+const CHACHA20_NOT_A_SIGNATURE: &str =
+    "ChaCha20-Poly1305 is an encryption format, use `rcli text encrypt` / `rcli text decrypt` instead";
+const X25519_NOT_A_SIGNATURE: &str =
+    "X25519 is a key agreement format, use `rcli text derive` to derive a ChaCha20-Poly1305 key";
+const MINISIGN_DETACHED: &str =
+    "minisign signatures are .minisig files with their own metadata, use `text sign --format minisign --output`";
+
+/// 签名文件的格式版本。
+const SIGNATURE_FILE_VERSION: u32 = 1;
+
+/// `text sign --output` 写出的签名文件（TOML），记录算法、密钥指纹与签名时间，
+/// 验证时无需事先知道签名格式。签名本身与直接输出的签名相同，只覆盖输入内容，元数据不参与签名。
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignatureFile {
+    version: u32,
+    algorithm: String,
+    key_fingerprint: String,
+    /// Unix 时间戳（秒）。
+    timestamp: u64,
+    signature: String,
+}
+
+impl SignatureFile {
+    pub fn format(&self) -> Result<TextSignFormat> {
+        self.algorithm.parse()
+    }
+
+    pub fn key_fingerprint(&self) -> &str {
+        &self.key_fingerprint
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn signature(&self) -> &str {
+        &self.signature
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read signature file {}", path))?;
+        data.parse()
+            .with_context(|| format!("Invalid signature file {}", path))
+    }
+}
+
+impl FromStr for SignatureFile {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let file: Self = toml::from_str(s)?;
+        if file.version != SIGNATURE_FILE_VERSION {
+            anyhow::bail!("Unsupported signature file version {}", file.version);
+        }
+        file.format()?;
+        Ok(file)
     }
 }
 
-pub fn process_sign(input: &str, key: &str, format: TextSignFormat) -> Result<String> {
-    let mut reader = get_reader(input)?;
-    let signature = match format {
+impl fmt::Display for SignatureFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# rcli detached signature")?;
+        write!(f, "{}", toml::to_string(self).map_err(|_| fmt::Error)?)
+    }
+}
+
+/// 签名并返回签名与签名密钥的指纹。
+fn sign_reader<R: Read>(reader: R, key: &str, format: TextSignFormat) -> Result<(Vec<u8>, String)> {
+    match format {
         TextSignFormat::Blake3 => {
             let blake3 = Blake3::load_key(key)?;
-            blake3.sign(&mut reader)?
+            let fingerprint = key_fingerprint(KeyKind::Blake3, &blake3.key);
+            Ok((blake3.sign(reader)?, fingerprint))
         }
         TextSignFormat::Ed25519 => {
-            let ed25519 = Ed25519::load_key(key)?;
-            ed25519.sign(&mut reader)?
+            let ed25519 = Ed25519::load(key, KeyKind::Ed25519Private)?;
+            let fingerprint = key_fingerprint(KeyKind::Ed25519Private, &ed25519.key);
+            Ok((ed25519.sign(reader)?, fingerprint))
         }
-        TextSignFormat::ChaCha20 => {
-            let chacha20 = ChaCha20::load_key(key)?;
-            chacha20.sign(&mut reader)?
-        }
-    };
-    let sign = URL_SAFE_NO_PAD.encode(signature);
-    println!("{}", sign);
-    Ok(sign)
+        TextSignFormat::HmacSha256 => sign_hmac::<Hmac<Sha256>, _>(reader, key),
+        TextSignFormat::HmacSha384 => sign_hmac::<Hmac<Sha384>, _>(reader, key),
+        TextSignFormat::HmacSha512 => sign_hmac::<Hmac<Sha512>, _>(reader, key),
+        TextSignFormat::EcdsaP256 => sign_pkey::<EcdsaP256Signer, _>(reader, key),
+        TextSignFormat::EcdsaSecp256k1 => sign_pkey::<EcdsaSecp256k1Signer, _>(reader, key),
+        TextSignFormat::RsaPss => sign_pkey::<RsaPssSigner, _>(reader, key),
+        TextSignFormat::Minisign => anyhow::bail!(MINISIGN_DETACHED),
+        TextSignFormat::ChaCha20 => anyhow::bail!(CHACHA20_NOT_A_SIGNATURE),
+        TextSignFormat::X25519 => anyhow::bail!(X25519_NOT_A_SIGNATURE),
+    }
 }
 
-pub fn process_verify(
-    input: &str,
+/// 验证签名，`expected_fingerprint` 不为空时先检查验证密钥是否为签名时使用的密钥。
+fn verify_reader<R: Read>(
+    reader: R,
     key: &str,
-    signature: &str,
+    signature: &[u8],
     format: TextSignFormat,
+    expected_fingerprint: Option<&str>,
 ) -> Result<bool> {
-    let mut reader = get_reader(input)?;
-    let signature = URL_SAFE_NO_PAD.decode(signature)?;
-    let signature = signature.as_slice();
-    let result = match format {
+    let check = |fingerprint: String| -> Result<()> {
+        match expected_fingerprint {
+            Some(expected) if expected != fingerprint => anyhow::bail!(
+                "The signature was made with key {} but the given key is {}",
+                expected,
+                fingerprint
+            ),
+            _ => Ok(()),
+        }
+    };
+    match format {
         TextSignFormat::Blake3 => {
             let blake3 = Blake3::load_key(key)?;
-            blake3.verify(&mut reader, signature)?
+            check(key_fingerprint(KeyKind::Blake3, &blake3.key))?;
+            blake3.verify(reader, signature)
         }
         TextSignFormat::Ed25519 => {
-            let ed25519 = Ed25519::load_key(key)?;
-            ed25519.verify(&mut reader, signature)?
+            let ed25519 = Ed25519::load(key, KeyKind::Ed25519Public)?;
+            check(key_fingerprint(KeyKind::Ed25519Public, &ed25519.key))?;
+            ed25519.verify(reader, signature)
+        }
+        TextSignFormat::HmacSha256 => verify_hmac::<Hmac<Sha256>, _>(reader, key, signature, check),
+        TextSignFormat::HmacSha384 => verify_hmac::<Hmac<Sha384>, _>(reader, key, signature, check),
+        TextSignFormat::HmacSha512 => verify_hmac::<Hmac<Sha512>, _>(reader, key, signature, check),
+        TextSignFormat::EcdsaP256 => {
+            verify_pkey::<EcdsaP256Verifier, _>(reader, key, signature, check)
         }
-        TextSignFormat::ChaCha20 => {
-            let chacha20 = ChaCha20::load_key(key)?;
-            chacha20.verify(&mut reader, signature)?
+        TextSignFormat::EcdsaSecp256k1 => {
+            verify_pkey::<EcdsaSecp256k1Verifier, _>(reader, key, signature, check)
         }
+        TextSignFormat::RsaPss => verify_pkey::<RsaPssVerifier, _>(reader, key, signature, check),
+        TextSignFormat::Minisign => anyhow::bail!(MINISIGN_DETACHED),
+        TextSignFormat::ChaCha20 => anyhow::bail!(CHACHA20_NOT_A_SIGNATURE),
+        TextSignFormat::X25519 => anyhow::bail!(X25519_NOT_A_SIGNATURE),
+    }
+}
+
+/// minisign 格式返回完整的 `.minisig` 内容（不支持 `hex` 编码），其他格式返回按 `encoding` 编码的签名。
+/// `ecdsa` 选择 ECDSA 签名的形式（定长的 r || s 或 DER），对其他格式无效。
+pub fn process_sign(
+    input: &str,
+    key: &str,
+    format: TextSignFormat,
+    encoding: SignatureEncoding,
+    ecdsa: EcdsaEncoding,
+) -> Result<String> {
+    if ecdsa == EcdsaEncoding::Der && !format.is_ecdsa() {
+        anyhow::bail!("DER signatures only apply to ECDSA, not {}", format);
+    }
+    if format == TextSignFormat::Minisign {
+        if encoding != SignatureEncoding::Base64 {
+            anyhow::bail!("minisign signatures have their own format, --encoding does not apply");
+        }
+        return process_minisign_sign(input, key, None);
+    }
+    let reader = get_reader(input)?;
+    let (signature, _) = sign_reader(reader, key, format)?;
+    let signature = match ecdsa {
+        EcdsaEncoding::Raw => signature,
+        EcdsaEncoding::Der => ecdsa_signature_to_der(format, &signature)?,
     };
-    println!("{}", result);
-    Ok(result)
+    Ok(encoding.encode(&signature))
+}
+
+/// 签名并生成带元数据的签名文件内容。
+pub fn process_sign_detached(
+    input: &str,
+    key: &str,
+    format: TextSignFormat,
+) -> Result<SignatureFile> {
+    sign_detached(get_reader(input)?, key, format)
+}
+
+pub(super) fn sign_detached<R: Read>(
+    reader: R,
+    key: &str,
+    format: TextSignFormat,
+) -> Result<SignatureFile> {
+    let (signature, key_fingerprint) = sign_reader(reader, key, format)?;
+    Ok(SignatureFile {
+        version: SIGNATURE_FILE_VERSION,
+        algorithm: format.to_string(),
+        key_fingerprint,
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        signature: URL_SAFE_NO_PAD.encode(signature),
+    })
+}
+
+pub fn process_verify(
+    input: &str,
+    key: &str,
+    signature: &str,
+    format: TextSignFormat,
+    encoding: SignatureEncoding,
+) -> Result<bool> {
+    if format == TextSignFormat::Minisign {
+        return Ok(process_minisign_verify(input, key, signature)?.is_some());
+    }
+    let reader = get_reader(input)?;
+    let signature = encoding.decode(signature)?;
+    verify_reader(reader, key, &signature, format, None)
+}
+
+/// 按签名文件中记录的算法验证，验证密钥与签名密钥的指纹不一致时报错。
+pub fn process_verify_detached(input: &str, key: &str, signature: &SignatureFile) -> Result<bool> {
+    verify_detached(get_reader(input)?, key, signature)
+}
+
+pub(super) fn verify_detached<R: Read>(
+    reader: R,
+    key: &str,
+    signature: &SignatureFile,
+) -> Result<bool> {
+    let decoded = URL_SAFE_NO_PAD.decode(&signature.signature)?;
+    verify_reader(
+        reader,
+        key,
+        &decoded,
+        signature.format()?,
+        Some(&signature.key_fingerprint),
+    )
 }
 
 pub fn create_key(format: TextSignFormat) -> Result<Vec<Vec<u8>>> {
@@ -262,6 +487,14 @@ pub fn create_key(format: TextSignFormat) -> Result<Vec<Vec<u8>>> {
         TextSignFormat::Blake3 => Blake3::generate(),
         TextSignFormat::Ed25519 => Ed25519::generate(),
         TextSignFormat::ChaCha20 => ChaCha20::generate(),
+        TextSignFormat::Minisign => minisign_keygen(None),
+        TextSignFormat::HmacSha256 => HmacKey::<Hmac<Sha256>>::generate(),
+        TextSignFormat::HmacSha384 => HmacKey::<Hmac<Sha384>>::generate(),
+        TextSignFormat::HmacSha512 => HmacKey::<Hmac<Sha512>>::generate(),
+        TextSignFormat::EcdsaP256 => EcdsaP256Signer::generate(),
+        TextSignFormat::EcdsaSecp256k1 => EcdsaSecp256k1Signer::generate(),
+        TextSignFormat::RsaPss => RsaPssSigner::generate(),
+        TextSignFormat::X25519 => X25519::generate(),
     }
 }
 
@@ -270,6 +503,10 @@ mod tests {
     use std::{env, io::Write, path::PathBuf};
 
     use super::*;
+    use crate::{
+        cli::text::{EncryptionAlgorithm, KeyEncoding},
+        process_decrypt, process_encrypt, EncryptionKey,
+    };
 
     fn get_fixture_path(filename: &str) -> PathBuf {
         let mut path = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
@@ -283,7 +520,9 @@ mod tests {
         let key = create_key(format).unwrap();
         let save_path = get_fixture_path("blake3.txt");
         let mut file = std::fs::File::create(save_path).unwrap();
-        file.write_all(&key[0]).unwrap();
+        let key = KeyFile::new(KeyKind::Blake3, &key[0]).unwrap();
+        file.write_all(&key.encode(KeyEncoding::Raw).unwrap())
+            .unwrap();
         file.flush().unwrap();
     }
 
@@ -293,7 +532,14 @@ mod tests {
         let key = binding.to_str().unwrap();
         let format = TextSignFormat::Blake3;
 
-        let sign = process_sign(input, key, format).unwrap();
+        let sign = process_sign(
+            input,
+            key,
+            format,
+            SignatureEncoding::Base64,
+            EcdsaEncoding::Raw,
+        )
+        .unwrap();
         let save_file_path = get_fixture_path("sign/blake3.sig");
         let save_path = get_fixture_path("sign");
         if !std::path::Path::new(&save_path).exists() {
@@ -314,7 +560,7 @@ mod tests {
         let mut signature = String::new();
         file.read_to_string(&mut signature).unwrap();
 
-        assert!(process_verify(input, key, &signature, format).unwrap());
+        assert!(process_verify(input, key, &signature, format, SignatureEncoding::Base64).unwrap());
     }
 
     fn test_create_key_ed25519() {
@@ -322,11 +568,17 @@ mod tests {
         let key = create_key(format).unwrap();
         let public_path = get_fixture_path("ed25519.pub");
         let privete_path = get_fixture_path("ed25519.priv");
+        let public_key = KeyFile::new(KeyKind::Ed25519Public, &key[1]).unwrap();
+        let private_key = KeyFile::new(KeyKind::Ed25519Private, &key[0]).unwrap();
         let mut public_file = std::fs::File::create(public_path).unwrap();
-        public_file.write_all(&key[1]).unwrap();
+        public_file
+            .write_all(&public_key.encode(KeyEncoding::Raw).unwrap())
+            .unwrap();
         public_file.flush().unwrap();
         let mut private_file = std::fs::File::create(privete_path).unwrap();
-        private_file.write_all(&key[0]).unwrap();
+        private_file
+            .write_all(&private_key.encode(KeyEncoding::Raw).unwrap())
+            .unwrap();
         private_file.flush().unwrap();
     }
 
@@ -336,7 +588,14 @@ mod tests {
         let key = binding.to_str().unwrap();
         let format = TextSignFormat::Ed25519;
         let save_file_path = get_fixture_path("sign/ed25519.sig");
-        let sign = process_sign(input, key, format).unwrap();
+        let sign = process_sign(
+            input,
+            key,
+            format,
+            SignatureEncoding::Base64,
+            EcdsaEncoding::Raw,
+        )
+        .unwrap();
         let save_path = get_fixture_path("sign");
         if !std::path::Path::new(&save_path).exists() {
             std::fs::create_dir(save_path).unwrap();
@@ -356,7 +615,7 @@ mod tests {
         let mut signature = String::new();
         file.read_to_string(&mut signature).unwrap();
 
-        assert!(process_verify(input, key, &signature, format).unwrap());
+        assert!(process_verify(input, key, &signature, format, SignatureEncoding::Base64).unwrap());
     }
 
     fn test_create_key_base64() {
@@ -364,38 +623,360 @@ mod tests {
         let key = create_key(format).unwrap();
         let save_path = get_fixture_path("base64.key");
         let mut file = std::fs::File::create(save_path).unwrap();
-        file.write_all(&key[0]).unwrap();
-        file.write_all(&key[1]).unwrap();
+        let key = KeyFile::new(KeyKind::ChaCha20, &key[0]).unwrap();
+        file.write_all(&key.encode(KeyEncoding::Raw).unwrap())
+            .unwrap();
         file.flush().unwrap();
     }
 
-    fn test_process_sign_base64() {
+    fn test_process_encrypt_base64() {
         let input = "Cargo.toml";
         let binding = get_fixture_path("base64.key");
         let key = binding.to_str().unwrap();
-        let format = TextSignFormat::ChaCha20;
-        let save_file_path = get_fixture_path("sign/chacha20.sig");
-        let sign = process_sign(input, key, format).unwrap();
         let save_path = get_fixture_path("sign");
         if !std::path::Path::new(&save_path).exists() {
             std::fs::create_dir(save_path).unwrap();
         }
-        let mut file = std::fs::File::create(save_file_path).unwrap();
-        file.write_all(sign.as_bytes()).unwrap();
-        file.flush().unwrap();
+        let save_file_path = get_fixture_path("sign/chacha20.enc");
+        process_encrypt(
+            input,
+            save_file_path.to_str(),
+            EncryptionKey::File(key.to_string()),
+            EncryptionAlgorithm::XChaCha20Poly1305,
+            b"rcli",
+        )
+        .unwrap();
+
+        // ChaCha20 不再伪装成签名算法
+        assert!(process_sign(
+            input,
+            key,
+            TextSignFormat::ChaCha20,
+            SignatureEncoding::Base64,
+            EcdsaEncoding::Raw,
+        )
+        .is_err());
+        assert!(process_verify(
+            input,
+            key,
+            "",
+            TextSignFormat::ChaCha20,
+            SignatureEncoding::Base64
+        )
+        .is_err());
     }
 
-    fn test_process_verify_base64() {
-        let input = "Cargo.toml";
+    fn test_process_decrypt_base64() {
         let binding = get_fixture_path("base64.key");
         let key = binding.to_str().unwrap();
-        let signature_file_path = get_fixture_path("sign/chacha20.sig");
-        let format = TextSignFormat::ChaCha20;
-        let mut file = std::fs::File::open(signature_file_path).unwrap();
-        let mut signature = String::new();
-        file.read_to_string(&mut signature).unwrap();
+        let encrypted = get_fixture_path("sign/chacha20.enc");
+        let decrypted = get_fixture_path("sign/chacha20.dec");
+        process_decrypt(
+            encrypted.to_str().unwrap(),
+            decrypted.to_str(),
+            EncryptionKey::File(key.to_string()),
+            b"rcli",
+        )
+        .unwrap();
+
+        assert_eq!(
+            std::fs::read(decrypted).unwrap(),
+            std::fs::read("Cargo.toml").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_legacy_blake3_key_as_ed25519() -> Result<()> {
+        // 旧版本直接保存 32 字节的 blake3 密钥，没有类型信息
+        let path = env::temp_dir().join(format!("rcli-legacy-blake3-{}", std::process::id()));
+        fs::write(&path, b"abcdefghijklmnopqrstuvwxyz012345")?;
+        let key = path.to_str().unwrap();
+        let err = process_sign(
+            "Cargo.toml",
+            key,
+            TextSignFormat::Ed25519,
+            SignatureEncoding::Base64,
+            EcdsaEncoding::Raw,
+        )
+        .unwrap_err();
+        fs::remove_file(&path)?;
+        let message = format!("{:#}", err);
+        assert!(
+            message.contains(KeyKind::Ed25519Private.hint()),
+            "{}",
+            message
+        );
+        assert!(message.contains("rcli text generate"), "{}", message);
+        Ok(())
+    }
+
+    #[test]
+    fn test_signature_file_roundtrip() -> Result<()> {
+        let private = get_fixture_path("keys/id_ed25519");
+        let public = get_fixture_path("keys/id_ed25519.pub");
+        let (private, public) = (private.to_str().unwrap(), public.to_str().unwrap());
+        let signature = process_sign_detached("Cargo.toml", private, TextSignFormat::Ed25519)?;
+        assert_eq!(
+            signature.key_fingerprint(),
+            "SHA256:/56mnvMMFk3NloAj+ztPG7UXoTzlkm11OZZn8rmCq8I"
+        );
+        // 与直接输出的签名相同
+        assert_eq!(
+            signature.signature(),
+            process_sign(
+                "Cargo.toml",
+                private,
+                TextSignFormat::Ed25519,
+                SignatureEncoding::Base64,
+                EcdsaEncoding::Raw,
+            )?
+        );
+
+        let parsed: SignatureFile = signature.to_string().parse()?;
+        assert_eq!(parsed.format()?, TextSignFormat::Ed25519);
+        assert_eq!(parsed.timestamp(), signature.timestamp());
+        assert!(process_verify_detached("Cargo.toml", public, &parsed)?);
+        assert!(!process_verify_detached("cliff.toml", public, &parsed)?);
+
+        // 用其他密钥验证时报告指纹不一致
+        let other = create_key(TextSignFormat::Ed25519)?;
+        let other_path = env::temp_dir().join(format!("rcli-sig-other-{}.pub", std::process::id()));
+        std::fs::write(
+            &other_path,
+            KeyFile::new(KeyKind::Ed25519Public, &other[1])?
+                .encode(crate::cli::text::KeyEncoding::Raw)?,
+        )?;
+        let err = process_verify_detached("Cargo.toml", other_path.to_str().unwrap(), &parsed)
+            .unwrap_err();
+        assert!(err.to_string().contains("SHA256:/56mnvMM"), "{}", err);
+        std::fs::remove_file(other_path)?;
+
+        let tampered = signature.to_string().replace("version = 1", "version = 2");
+        assert!(tampered.parse::<SignatureFile>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ed25519ph_rfc8032() {
+        let secret =
+            hex::decode("833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42")
+                .unwrap();
+        let public =
+            hex::decode("ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf")
+                .unwrap();
+        let expected = hex::decode(
+            "98a70222f0b8121aa9d30f813d683f809e462b469c7ff87639499bb94e6dae41\
+             31f85042463c2a355a2003d062adf5aaa10b8c61e636062aaad11c2a26083406",
+        )
+        .unwrap();
+
+        let signature = Ed25519::new(secret.try_into().unwrap())
+            .sign(&b"abc"[..])
+            .unwrap();
+        assert_eq!(signature, expected);
+        let verifier = Ed25519::new(public.try_into().unwrap());
+        assert!(verifier.verify(&b"abc"[..], &signature).unwrap());
+        assert!(!verifier.verify(&b"abd"[..], &signature).unwrap());
+    }
+
+    #[test]
+    fn test_blake3_streaming_matches_one_shot() {
+        let key = [7u8; 32];
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let signature = Blake3::new(key).sign(data.as_slice()).unwrap();
+        assert_eq!(
+            signature,
+            blake3::keyed_hash(&key, &data).as_bytes().to_vec()
+        );
+    }
+
+    #[test]
+    fn test_hmac_rfc4231() {
+        // RFC 4231 test case 2
+        let data = &b"what do ya want for nothing?"[..];
+        let cases = [
+            (
+                HmacKey::<Hmac<Sha256>>::new(b"Jefe").sign(data).unwrap(),
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            ),
+            (
+                HmacKey::<Hmac<Sha384>>::new(b"Jefe").sign(data).unwrap(),
+                "af45d2e376484031617f78d2b58a6b1b9c7ef464f5a01b47e42ec3736322445e\
+                 8e2240ca5e69e2c78b3239ecfab21649",
+            ),
+            (
+                HmacKey::<Hmac<Sha512>>::new(b"Jefe").sign(data).unwrap(),
+                "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+                 9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+            ),
+        ];
+        for (signature, expected) in cases {
+            assert_eq!(hex::encode(signature), expected);
+        }
+        let verifier = HmacKey::<Hmac<Sha256>>::new(b"Jefe");
+        let signature = verifier.sign(data).unwrap();
+        assert!(verifier.verify(data, &signature).unwrap());
+        assert!(!verifier
+            .verify(&b"what do ya want for something?"[..], &signature)
+            .unwrap());
+    }
 
-        assert!(process_verify(input, key, &signature, format).unwrap());
+    #[test]
+    fn test_hmac_webhook_secret() -> Result<()> {
+        // GitHub 文档中 X-Hub-Signature-256 的示例
+        let dir = std::env::temp_dir().join(format!("rcli-hmac-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let secret = dir.join("webhook.secret");
+        std::fs::write(&secret, "It's a Secret to Everybody\n")?;
+        let payload = dir.join("payload");
+        std::fs::write(&payload, "Hello, World!")?;
+        let (secret, payload) = (secret.to_str().unwrap(), payload.to_str().unwrap());
+
+        let expected = "757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+        let format = TextSignFormat::HmacSha256;
+        assert_eq!(
+            process_sign(
+                payload,
+                secret,
+                format,
+                SignatureEncoding::Hex,
+                EcdsaEncoding::Raw
+            )?,
+            expected
+        );
+        let header = format!("sha256={}", expected);
+        assert!(process_verify(
+            payload,
+            secret,
+            &header,
+            format,
+            SignatureEncoding::Hex
+        )?);
+        let base64 = process_sign(
+            payload,
+            secret,
+            format,
+            SignatureEncoding::Base64,
+            EcdsaEncoding::Raw,
+        )?;
+        assert!(process_verify(
+            payload,
+            secret,
+            &base64,
+            format,
+            SignatureEncoding::Base64
+        )?);
+        assert!(!process_verify(
+            payload,
+            secret,
+            &base64,
+            TextSignFormat::HmacSha512,
+            SignatureEncoding::Base64
+        )?);
+
+        // 生成的密钥文件带有类型，不能当作其他算法的密钥使用
+        let key = dir.join("hmac.key");
+        let generated = KeyFile::new(KeyKind::Hmac, &create_key(format)?[0])?;
+        std::fs::write(&key, generated.encode(crate::cli::text::KeyEncoding::Pem)?)?;
+        let key = key.to_str().unwrap();
+        let signature = process_sign(
+            payload,
+            key,
+            TextSignFormat::HmacSha384,
+            SignatureEncoding::Hex,
+            EcdsaEncoding::Raw,
+        )?;
+        assert_eq!(signature.len(), 96);
+        assert!(process_verify(
+            payload,
+            key,
+            &signature,
+            TextSignFormat::HmacSha384,
+            SignatureEncoding::Hex
+        )?);
+        assert!(process_sign(
+            payload,
+            key,
+            TextSignFormat::Blake3,
+            SignatureEncoding::Base64,
+            EcdsaEncoding::Raw,
+        )
+        .is_err());
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    /// 合法签名必须通过；截断、追加字节以及任意一位翻转后都必须返回 `Ok(false)`，
+    /// 而不是报错或通过。
+    fn assert_rejects_tampering(verifier: &impl TextVerify, data: &[u8], signature: &[u8]) {
+        assert!(verifier.verify(data, signature).unwrap());
+        let mut tampered = vec![
+            Vec::new(),
+            signature[..signature.len() - 1].to_vec(),
+            signature[1..].to_vec(),
+            [signature, &[0]].concat(),
+            [signature, signature].concat(),
+        ];
+        // DER 与 RSA 签名较长，验证也慢，每个字节只翻转一位（位置依次错开）
+        let step = if signature.len() > 64 { 9 } else { 1 };
+        for bit in (0..signature.len() * 8).step_by(step) {
+            let mut flipped = signature.to_vec();
+            flipped[bit / 8] ^= 1 << (bit % 8);
+            tampered.push(flipped);
+        }
+        for signature in tampered {
+            assert!(
+                !verifier.verify(data, &signature).unwrap(),
+                "tampered signature accepted: {}",
+                hex::encode(&signature)
+            );
+        }
+        assert!(!verifier.verify(&b"other data"[..], signature).unwrap());
+    }
+
+    #[test]
+    fn test_verify_rejects_tampered_signatures() {
+        let data = &b"release artifact"[..];
+
+        let blake3 = Blake3::new([7u8; 32]);
+        assert_rejects_tampering(&blake3, data, &blake3.sign(data).unwrap());
+
+        let keys = Ed25519::generate().unwrap();
+        let signature = Ed25519::new(keys[0].clone().try_into().unwrap())
+            .sign(data)
+            .unwrap();
+        let verifier = Ed25519::new(keys[1].clone().try_into().unwrap());
+        assert_rejects_tampering(&verifier, data, &signature);
+
+        let hmac = HmacKey::<Hmac<Sha256>>::new(b"webhook secret");
+        assert_rejects_tampering(&hmac, data, &hmac.sign(data).unwrap());
+        let hmac = HmacKey::<Hmac<Sha384>>::new(b"webhook secret");
+        assert_rejects_tampering(&hmac, data, &hmac.sign(data).unwrap());
+        let hmac = HmacKey::<Hmac<Sha512>>::new(b"webhook secret");
+        assert_rejects_tampering(&hmac, data, &hmac.sign(data).unwrap());
+
+        let signature = EcdsaP256Signer::load_key("fixtures/pkey/p256.sec1.pem")
+            .unwrap()
+            .sign(data)
+            .unwrap();
+        let verifier = EcdsaP256Verifier::load_key("fixtures/pkey/p256.pub").unwrap();
+        assert_rejects_tampering(&verifier, data, &signature);
+        let der = ecdsa_signature_to_der(TextSignFormat::EcdsaP256, &signature).unwrap();
+        assert_rejects_tampering(&verifier, data, &der);
+
+        let signature = EcdsaSecp256k1Signer::load_key("fixtures/pkey/secp256k1.pem")
+            .unwrap()
+            .sign(data)
+            .unwrap();
+        let verifier = EcdsaSecp256k1Verifier::load_key("fixtures/pkey/secp256k1.pub").unwrap();
+        assert_rejects_tampering(&verifier, data, &signature);
+
+        let signature = RsaPssSigner::load_key("fixtures/pkey/rsa.pem")
+            .unwrap()
+            .sign(data)
+            .unwrap();
+        let verifier = RsaPssVerifier::load_key("fixtures/pkey/rsa.pub").unwrap();
+        assert_rejects_tampering(&verifier, data, &signature);
     }
 
     #[test]
@@ -407,7 +988,7 @@ mod tests {
         test_process_sign_ed25519();
         test_process_verify_ed25519();
         test_create_key_base64();
-        test_process_sign_base64();
-        test_process_verify_base64();
+        test_process_encrypt_base64();
+        test_process_decrypt_base64();
     }
 }
@@ -1,4 +1,4 @@
-use std::io::Read;
+use std::io::{Read, Write};
 
 pub fn get_reader(input: &str) -> Result<Box<dyn Read>, anyhow::Error> {
     let reader: Box<dyn Read> = if input == "-" {
@@ -8,3 +8,106 @@ pub fn get_reader(input: &str) -> Result<Box<dyn Read>, anyhow::Error> {
     };
     Ok(reader)
 }
+
+pub fn get_writer(output: Option<&str>) -> Result<Box<dyn Write>, anyhow::Error> {
+    let writer: Box<dyn Write> = match output {
+        None | Some("-") => Box::new(std::io::stdout()),
+        Some(output) => Box::new(std::fs::File::create(output)?),
+    };
+    Ok(writer)
+}
+
+// 命令的退出码（成功为 0），完整的表见 README。
+
+/// I/O、密钥、格式等错误。
+pub const EXIT_ERROR: u8 = 1;
+/// 参数错误，由 clap 在解析参数时直接退出。
+pub const EXIT_USAGE: u8 = 2;
+/// 验证未通过：签名无效、摘要不匹配、令牌或验证码无效。
+pub const EXIT_VERIFY_FAILED: u8 = 3;
+
+/// 验证未通过的错误，以 `EXIT_VERIFY_FAILED` 退出，与其他错误区分。
+/// `message` 为 `None` 表示命令已经输出了验证结果（或处于 `--quiet` 模式），不再打印错误。
+#[derive(Debug)]
+pub struct VerificationFailed {
+    message: Option<String>,
+}
+
+impl VerificationFailed {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: Some(message.into()),
+        }
+    }
+
+    pub fn reported() -> Self {
+        Self { message: None }
+    }
+}
+
+impl std::fmt::Display for VerificationFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.message.as_deref().unwrap_or("Verification failed")
+        )
+    }
+}
+
+impl std::error::Error for VerificationFailed {}
+
+fn verification_failure(err: &anyhow::Error) -> Option<&VerificationFailed> {
+    err.chain()
+        .find_map(|e| e.downcast_ref::<VerificationFailed>())
+}
+
+/// 错误对应的退出码。
+pub fn exit_code(err: &anyhow::Error) -> u8 {
+    match verification_failure(err) {
+        Some(_) => EXIT_VERIFY_FAILED,
+        None => EXIT_ERROR,
+    }
+}
+
+/// 是否需要由 `main` 打印错误：已经输出过结果的验证失败不再重复打印。
+pub fn should_report(err: &anyhow::Error) -> bool {
+    verification_failure(err).is_none_or(|e| e.message.is_some())
+}
+
+/// `--quiet` 模式下验证失败不输出任何内容，只通过退出码反映结果。
+pub fn quiet_verification<T>(result: anyhow::Result<T>, quiet: bool) -> anyhow::Result<T> {
+    result.map_err(|err| {
+        if quiet && verification_failure(&err).is_some() {
+            VerificationFailed::reported().into()
+        } else {
+            err
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+
+    use super::*;
+
+    #[test]
+    fn test_exit_code() {
+        let err = anyhow::anyhow!("No such file");
+        assert_eq!(exit_code(&err), EXIT_ERROR);
+        assert!(should_report(&err));
+
+        let err = Err::<(), _>(VerificationFailed::new("bad signature"))
+            .context("Failed to verify dist")
+            .unwrap_err();
+        assert_eq!(exit_code(&err), EXIT_VERIFY_FAILED);
+        assert!(should_report(&err));
+
+        let err = quiet_verification::<()>(Err(err), true).unwrap_err();
+        assert_eq!(exit_code(&err), EXIT_VERIFY_FAILED);
+        assert!(!should_report(&err));
+        let err = quiet_verification::<()>(Err(anyhow::anyhow!("I/O")), true).unwrap_err();
+        assert!(should_report(&err));
+    }
+}